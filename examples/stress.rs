@@ -0,0 +1,145 @@
+//! Randomized stress test: runs a seeded mix of operations against both
+//! heaps and a `BTreeSet` oracle and asserts that every pop agrees.
+//!
+//! ```text
+//! cargo run --release --example stress -- [seed] [ops]
+//! ```
+//!
+//! Both heaps break key ties by id, so their pop order is fully determined
+//! and must match the oracle's `(key, id)` order exactly.
+
+use std::collections::BTreeSet;
+use std::env;
+
+use heapix::{FibHeap, MinHeap};
+
+// xorshift64*, good enough to shuffle operations and reproducible per seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+const MAX_ID: usize = 4096;
+const CHECK_EVERY: usize = 10_000;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let seed: u64 = args
+        .next()
+        .map_or(1, |s| s.parse().expect("seed must be a u64"));
+    let ops: usize = args
+        .next()
+        .map_or(1_000_000, |s| s.parse().expect("ops must be a usize"));
+
+    let mut rng = Rng(seed.max(1));
+    let mut mh: MinHeap<i64> = MinHeap::builder().stable(true).build().unwrap();
+    let mut fh: FibHeap<i64> = FibHeap::new();
+    fh.set_stable(true);
+
+    let mut oracle: BTreeSet<(i64, usize)> = BTreeSet::new();
+    let mut key_of: Vec<Option<i64>> = vec![None; MAX_ID];
+    // live ids, with each id's index in it for O(1) random picks
+    let mut live: Vec<usize> = Vec::new();
+    let mut live_idx: Vec<usize> = vec![usize::MAX; MAX_ID];
+
+    let mut pops = 0usize;
+    for step in 0..ops {
+        match rng.below(100) {
+            // insert a fresh id
+            0..=44 => {
+                let id = rng.below(MAX_ID as u64) as usize;
+                if key_of[id].is_none() {
+                    let key = rng.below(1_000_000) as i64;
+                    mh.insert((id, key));
+                    fh.insert((id, key));
+                    oracle.insert((key, id));
+                    key_of[id] = Some(key);
+                    live_idx[id] = live.len();
+                    live.push(id);
+                }
+            }
+            // decrease a random live id, sometimes below the current min
+            45..=69 if !live.is_empty() => {
+                let id = live[rng.below(live.len() as u64) as usize];
+                let old = key_of[id].unwrap();
+                let new = if rng.below(4) == 0 {
+                    oracle.first().unwrap().0 - 1 - rng.below(10) as i64
+                } else {
+                    old - 1 - rng.below(1000) as i64
+                };
+                mh.decrease_key(id, new);
+                fh.decrease_key(id, new);
+                oracle.remove(&(old, id));
+                oracle.insert((new, id));
+                key_of[id] = Some(new);
+            }
+            // clear everything mid-stream, rarely
+            70 if rng.below(50) == 0 => {
+                mh.clear();
+                fh.clear();
+                for &id in &live {
+                    key_of[id] = None;
+                    live_idx[id] = usize::MAX;
+                }
+                live.clear();
+                oracle.clear();
+            }
+            // pop and compare
+            _ => {
+                let expected = oracle.pop_first().map(|(key, id)| (id, key));
+                let from_min = mh.delete_min();
+                let from_fib = fh.delete_min();
+                assert_eq!(
+                    from_min, expected,
+                    "MinHeap diverged at step {step} (seed {seed})"
+                );
+                assert_eq!(
+                    from_fib, expected,
+                    "FibHeap diverged at step {step} (seed {seed})"
+                );
+
+                if let Some((id, _)) = expected {
+                    key_of[id] = None;
+                    let i = live_idx[id];
+                    live.swap_remove(i);
+                    if i < live.len() {
+                        live_idx[live[i]] = i;
+                    }
+                    live_idx[id] = usize::MAX;
+                    pops += 1;
+                }
+            }
+        }
+
+        if step % CHECK_EVERY == 0 {
+            assert_eq!(mh.len(), oracle.len());
+            assert_eq!(fh.len(), oracle.len());
+            mh.assert_valid();
+            fh.assert_valid();
+            #[cfg(debug_assertions)]
+            if let Some(&(key, _)) = oracle.first() {
+                fh.assert_heap_ok(key);
+            }
+        }
+    }
+
+    // drain what's left, still in lockstep
+    while let Some((key, id)) = oracle.pop_first() {
+        assert_eq!(mh.delete_min(), Some((id, key)));
+        assert_eq!(fh.delete_min(), Some((id, key)));
+        pops += 1;
+    }
+    assert!(mh.is_empty() && fh.is_empty());
+
+    println!("seed {seed}: {ops} ops, {pops} pops, all heaps agree");
+}
@@ -0,0 +1,129 @@
+//! Graph algorithms built on the addressable heaps.
+//!
+//! Graphs are adjacency lists: `adj[u]` holds `(v, weight)` for every edge
+//! `u -> v`. Weights must be non-negative.
+
+use std::ops::Add;
+
+use crate::{FibHeap, HeapError};
+
+/// Addition that reports overflow instead of wrapping, implemented for the
+/// primitive integers.
+pub trait CheckedAdd: Sized {
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($t:ty),*) => {$(
+        impl CheckedAdd for $t {
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *rhs)
+            }
+        }
+    )*};
+}
+
+impl_checked_add!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Shortest distances from `source`, `None` for unreachable nodes. The
+/// source sits at `K::default()` (zero for numbers). Integer sums that
+/// overflow wrap or panic like plain `+`; see [`dijkstra_checked`].
+pub fn dijkstra<K>(adj: &[Vec<(usize, K)>], source: usize) -> Vec<Option<K>>
+where
+    K: PartialOrd + Clone + Default + Add<Output = K>,
+{
+    run(adj, source, |d, w| Ok(d.clone() + w.clone())).unwrap()
+}
+
+/// [`dijkstra`] with overflow-checked sums. Any relaxation whose distance
+/// doesn't fit in `K` fails with `HeapError::Overflow(v)` for the edge's
+/// target `v`, even if that path wouldn't have been the shortest, rather
+/// than letting a wrapped sum pass for a short distance.
+pub fn dijkstra_checked<K>(
+    adj: &[Vec<(usize, K)>],
+    source: usize,
+) -> Result<Vec<Option<K>>, HeapError>
+where
+    K: PartialOrd + Clone + Default + CheckedAdd,
+{
+    run(adj, source, |d, w| d.checked_add(w).ok_or(()))
+}
+
+fn run<K, F>(adj: &[Vec<(usize, K)>], source: usize, add: F) -> Result<Vec<Option<K>>, HeapError>
+where
+    K: PartialOrd + Clone + Default,
+    F: Fn(&K, &K) -> Result<K, ()>,
+{
+    let mut dist: Vec<Option<K>> = vec![None; adj.len()];
+    let mut done = vec![false; adj.len()];
+    let mut heap = FibHeap::new();
+    dist[source] = Some(K::default());
+    heap.insert((source, K::default()));
+
+    while let Some((u, du)) = heap.delete_min() {
+        done[u] = true;
+        for (v, w) in &adj[u] {
+            if done[*v] {
+                // still checked, a wrapped sum could look shorter
+                add(&du, w).map_err(|_| HeapError::Overflow(*v))?;
+                continue;
+            }
+            let cand = add(&du, w).map_err(|_| HeapError::Overflow(*v))?;
+            match &dist[*v] {
+                None => heap.insert((*v, cand.clone())),
+                Some(old) if cand < *old => heap.decrease_key(*v, cand.clone()),
+                Some(_) => continue,
+            }
+            dist[*v] = Some(cand);
+        }
+    }
+    Ok(dist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dijkstra, dijkstra_checked};
+    use crate::HeapError;
+
+    #[test]
+    fn shortest_paths() {
+        let adj: Vec<Vec<(usize, u32)>> = vec![
+            vec![(1, 7), (2, 9), (5, 14)],
+            vec![(0, 7), (2, 10), (3, 15)],
+            vec![(0, 9), (1, 10), (3, 11), (5, 2)],
+            vec![(1, 15), (2, 11), (4, 6)],
+            vec![(3, 6), (5, 9)],
+            vec![(0, 14), (2, 2), (4, 9)],
+            vec![],
+        ];
+        let expected = vec![
+            Some(0),
+            Some(7),
+            Some(9),
+            Some(20),
+            Some(20),
+            Some(11),
+            None,
+        ];
+        assert_eq!(dijkstra(&adj, 0), expected);
+        assert_eq!(dijkstra_checked(&adj, 0), Ok(expected));
+    }
+
+    #[test]
+    fn checked_reports_overflow() {
+        // 0 -> 2 costs 1000 directly; through 1 the u32 sum wraps to 89
+        let adj: Vec<Vec<(usize, u32)>> =
+            vec![vec![(1, u32::MAX - 10), (2, 1000)], vec![(2, 100)], vec![]];
+        assert_eq!(dijkstra_checked(&adj, 0), Err(HeapError::Overflow(2)));
+
+        let fits: Vec<Vec<(usize, u64)>> = vec![
+            vec![(1, u32::MAX as u64 - 10), (2, 1000)],
+            vec![(2, 100)],
+            vec![],
+        ];
+        assert_eq!(
+            dijkstra_checked(&fits, 0),
+            Ok(vec![Some(0), Some(u32::MAX as u64 - 10), Some(1000)])
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// a binary min-heap of ids whose keys live in a slice owned by the caller.
+// the heap itself stores only ids and positions; every comparison indexes
+// the borrowed slice, so id `i` has key `keys[i]` and large keys are never
+// copied.
+//
+// the slice is borrowed shared for 'a, so to change keys while the heap is
+// alive use a slice of cells (e.g. `&[Cell<f64>]`, which is PartialOrd),
+// write the new key and then tell the heap with `decrease_key(id)`
+pub struct BorrowedKeyHeap<'a, K> {
+    keys: &'a [K],
+    heap: Vec<usize>,      // ids in heap order
+    positions: Vec<usize>, // id -> slot in heap, usize::MAX if absent
+}
+
+impl<'a, K: PartialOrd> BorrowedKeyHeap<'a, K> {
+    pub fn new(keys: &'a [K]) -> Self {
+        BorrowedKeyHeap {
+            keys,
+            heap: Vec::new(),
+            positions: vec![usize::MAX; keys.len()],
+        }
+    }
+
+    pub fn keys(&self) -> &'a [K] {
+        self.keys
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.positions.get(id).is_some_and(|&p| p != usize::MAX)
+    }
+
+    // add id with key keys[id]. panics if id is out of the slice's range,
+    // inserting an id twice is ignored
+    pub fn insert(&mut self, id: usize) {
+        assert!(id < self.keys.len(), "id {id} is outside the key slice");
+        if self.contains(id) {
+            return;
+        }
+        self.heap.push(id);
+        self.positions[id] = self.heap.len() - 1;
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    pub fn peek(&self) -> Option<(usize, &'a K)> {
+        self.heap.first().map(|&id| (id, &self.keys[id]))
+    }
+
+    pub fn pop(&mut self) -> Option<(usize, &'a K)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let id = self.heap.swap_remove(0);
+        self.positions[id] = usize::MAX;
+        if let Some(&moved) = self.heap.first() {
+            self.positions[moved] = 0;
+            self.sift_down(0);
+        }
+        Some((id, &self.keys[id]))
+    }
+
+    // the caller lowered keys[id]; restore the heap order. no-op for ids not
+    // in the heap
+    pub fn decrease_key(&mut self, id: usize) {
+        if self.contains(id) {
+            self.sift_up(self.positions[id]);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for id in self.heap.drain(..) {
+            self.positions[id] = usize::MAX;
+        }
+    }
+
+    // incomparable keys (NaN) never count as less, so they just stay put
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.keys[self.heap[a]] < self.keys[self.heap[b]]
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a]] = a;
+        self.positions[self.heap[b]] = b;
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.less(index, parent) {
+                break;
+            }
+            self.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        let len = self.heap.len();
+        loop {
+            let left = 2 * index + 1;
+            if left >= len {
+                break;
+            }
+            let right = left + 1;
+            let smaller = if right < len && self.less(right, left) {
+                right
+            } else {
+                left
+            };
+            if !self.less(smaller, index) {
+                break;
+            }
+            self.swap(smaller, index);
+            index = smaller;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BorrowedKeyHeap;
+    use std::cell::Cell;
+
+    #[test]
+    fn pops_in_slice_order() {
+        let weights: Vec<f64> = vec![4.5, 0.25, 9.0, 3.0, 7.75, 0.5, 6.0];
+
+        let mut h = BorrowedKeyHeap::new(&weights);
+        for id in 0..weights.len() {
+            h.insert(id);
+        }
+        h.insert(3);
+        assert_eq!(h.len(), weights.len());
+        assert_eq!(h.peek(), Some((1, &0.25)));
+
+        let popped: Vec<usize> = std::iter::from_fn(|| h.pop().map(|(id, _)| id)).collect();
+        assert_eq!(popped, vec![1, 5, 3, 0, 6, 4, 2]);
+        assert!(h.is_empty() && !h.contains(1));
+    }
+
+    #[test]
+    fn decrease_through_cells() {
+        let weights: Vec<Cell<f64>> = [5.0, 3.0, 8.0, 1.0].map(Cell::new).to_vec();
+
+        let mut h = BorrowedKeyHeap::new(&weights);
+        for id in 0..weights.len() {
+            h.insert(id);
+        }
+        weights[2].set(0.5);
+        h.decrease_key(2);
+        assert_eq!(h.pop().map(|(id, _)| id), Some(2));
+        assert_eq!(h.pop().map(|(id, _)| id), Some(3));
+        assert_eq!(h.len(), 2);
+
+        h.clear();
+        assert!(h.is_empty() && !h.contains(0));
+    }
+}
@@ -0,0 +1,32 @@
+//! Smallest-value bound for numeric keys.
+
+/// Keys with a value no other key orders before, the "minus infinity" of
+/// the textbook `delete`. Implemented for the primitive numbers (floats use
+/// `-inf`).
+pub trait Bounded {
+    fn min_value() -> Self;
+}
+
+macro_rules! impl_bounded {
+    ($($t:ty),*) => {$(
+        impl Bounded for $t {
+            fn min_value() -> Self {
+                <$t>::MIN
+            }
+        }
+    )*};
+}
+
+impl_bounded!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Bounded for f32 {
+    fn min_value() -> Self {
+        f32::NEG_INFINITY
+    }
+}
+
+impl Bounded for f64 {
+    fn min_value() -> Self {
+        f64::NEG_INFINITY
+    }
+}
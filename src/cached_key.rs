@@ -0,0 +1,181 @@
+use crate::MinHeap;
+
+// values ordered by key_fn(value) like MinHeap::new_by_key, but the key is
+// computed once per insert and cached in the heap, so sifts compare plain
+// keys and never call key_fn. costs one P per entry. if a value is changed
+// through value_mut, call recompute_key(id) to refresh its cached key
+pub struct CachedKeyHeap<T, P, F> {
+    heap: MinHeap<P>,
+    // values by id, None for ids not in the heap
+    values: Vec<Option<T>>,
+    key_fn: F,
+}
+
+impl<T, P, F> CachedKeyHeap<T, P, F>
+where
+    P: PartialOrd + Clone,
+    F: Fn(&T) -> P,
+{
+    pub fn new(key_fn: F) -> Self {
+        CachedKeyHeap {
+            heap: MinHeap::new(),
+            values: Vec::new(),
+            key_fn,
+        }
+    }
+
+    // O(n): key_fn runs once per item, then one build_heap. panics on
+    // duplicate ids like build_heap
+    pub fn from_values(key_fn: F, items: Vec<(usize, T)>) -> Self {
+        let mut values = Vec::new();
+        let mut keyed = Vec::with_capacity(items.len());
+        for (id, value) in items {
+            keyed.push((id, key_fn(&value)));
+            if id >= values.len() {
+                values.resize_with(id + 1, || None);
+            }
+            values[id] = Some(value);
+        }
+        CachedKeyHeap {
+            heap: MinHeap::build_heap(keyed),
+            values,
+            key_fn,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.heap.contains(id)
+    }
+
+    // ids must be unique, same as for MinHeap
+    pub fn insert(&mut self, id: usize, value: T) {
+        let key = (self.key_fn)(&value);
+        if id >= self.values.len() {
+            self.values.resize_with(id + 1, || None);
+        }
+        self.values[id] = Some(value);
+        self.heap.insert((id, key));
+    }
+
+    // the min id with its value and cached key
+    pub fn get_min(&self) -> Option<(usize, &T, &P)> {
+        self.heap
+            .get_min()
+            .map(|(id, key)| (*id, self.values[*id].as_ref().unwrap(), key))
+    }
+
+    pub fn delete_min(&mut self) -> Option<(usize, T)> {
+        let (id, _) = self.heap.delete_min()?;
+        Some((id, self.values[id].take().unwrap()))
+    }
+
+    pub fn value(&self, id: usize) -> Option<&T> {
+        self.values.get(id)?.as_ref()
+    }
+
+    // edits don't reach the cached key until recompute_key(id)
+    pub fn value_mut(&mut self, id: usize) -> Option<&mut T> {
+        self.values.get_mut(id)?.as_mut()
+    }
+
+    // re-run key_fn on the (mutated) value and move it in whichever
+    // direction the new key requires. no-op for absent ids
+    pub fn recompute_key(&mut self, id: usize) {
+        if let Some(value) = self.value(id) {
+            let key = (self.key_fn)(value);
+            self.heap.change_key(id, key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedKeyHeap;
+    use crate::MinHeap;
+    use std::cell::Cell;
+
+    #[derive(Clone, Debug, PartialEq, PartialOrd)]
+    struct Task {
+        name: String,
+        cost: u32,
+    }
+
+    fn tasks(n: usize) -> Vec<(usize, Task)> {
+        (0..n)
+            .map(|id| {
+                let cost = ((id * 7919) % 1013) as u32 + 1;
+                (
+                    id,
+                    Task {
+                        name: format!("t{id}"),
+                        cost,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extractor_runs_once_per_item() {
+        let n = 1000;
+        let calls = Cell::new(0usize);
+        let key_fn = |t: &Task| {
+            calls.set(calls.get() + 1);
+            t.cost
+        };
+
+        let mut h = CachedKeyHeap::from_values(key_fn, tasks(n));
+        assert_eq!(calls.get(), n);
+        let mut last = 0;
+        while let Some((_, t)) = h.delete_min() {
+            assert!(t.cost >= last);
+            last = t.cost;
+        }
+        // popping compares cached keys only
+        assert_eq!(calls.get(), n);
+
+        // the uncached extractor runs on both sides of every comparison
+        let uncached = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&uncached);
+        let mut mh = MinHeap::new_by_key(move |t: &Task| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            t.cost
+        });
+        for item in tasks(n) {
+            mh.insert(item);
+        }
+        while mh.delete_min().is_some() {}
+        assert!(uncached.load(std::sync::atomic::Ordering::Relaxed) > 10 * n);
+    }
+
+    #[test]
+    fn recompute_after_mutation() {
+        let mut h = CachedKeyHeap::new(|t: &Task| t.cost);
+        for (id, t) in tasks(20) {
+            h.insert(id, t);
+        }
+        let (min_id, _, &min_cost) = h.get_min().unwrap();
+
+        // make id 5 the cheapest; invisible until recomputed
+        h.value_mut(5).unwrap().cost = 0;
+        assert_eq!(h.get_min().map(|m| m.0), Some(min_id));
+        h.recompute_key(5);
+        assert_eq!(h.get_min().map(|m| (m.0, *m.2)), Some((5, 0)));
+
+        // and push it back past everything else
+        h.value_mut(5).unwrap().cost = 5000;
+        h.recompute_key(5);
+        assert_eq!(h.get_min().map(|m| *m.2), Some(min_cost));
+        let last = std::iter::from_fn(|| h.delete_min()).last().unwrap();
+        assert_eq!(last.0, 5);
+        assert!(h.is_empty() && h.value(5).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapError {
+    // the id is not currently in the heap
+    NotPresent(usize),
+    // the same id appeared more than once
+    DuplicateId(usize),
+    // a decrease for this id didn't make its key smaller
+    KeyNotSmaller(usize),
+    // partial_cmp returned None, e.g. for a NaN key
+    Incomparable,
+    // a generational handle refers to an entry that is gone or was replaced
+    StaleHandle,
+    // usize::MAX can't be an id: positions would need usize::MAX + 1 slots
+    IdTooLarge(usize),
+    // the heap is at its max_len and refuses to grow
+    CapacityExceeded,
+    // a key computed for this id doesn't fit in the key type
+    Overflow(usize),
+    // raw parts handed to try_from_raw_parts don't form a valid heap
+    InvalidLayout(&'static str),
+    // contradictory builder options
+    InvalidConfig(&'static str),
+}
+
+impl std::fmt::Display for HeapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeapError::NotPresent(id) => write!(f, "id {id} is not in the heap"),
+            HeapError::DuplicateId(id) => write!(f, "id {id} is already in the heap"),
+            HeapError::KeyNotSmaller(id) => {
+                write!(f, "new key for id {id} is not smaller than the current one")
+            }
+            HeapError::Incomparable => write!(f, "keys are not comparable (NaN?)"),
+            HeapError::StaleHandle => write!(f, "handle refers to an entry that is gone"),
+            HeapError::IdTooLarge(id) => write!(f, "id {id} is too large"),
+            HeapError::CapacityExceeded => write!(f, "heap is at its maximum length"),
+            HeapError::Overflow(id) => write!(f, "key for id {id} overflows the key type"),
+            HeapError::InvalidLayout(why) => write!(f, "invalid heap layout: {why}"),
+            HeapError::InvalidConfig(why) => write!(f, "invalid heap configuration: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for HeapError {}
+
+#[cfg(test)]
+mod tests {
+    use super::HeapError;
+    use crate::MinHeap;
+    use std::error::Error;
+
+    #[test]
+    fn display_messages() {
+        let cases = [
+            (HeapError::NotPresent(3), "id 3 is not in the heap"),
+            (HeapError::DuplicateId(4), "id 4 is already in the heap"),
+            (
+                HeapError::KeyNotSmaller(5),
+                "new key for id 5 is not smaller than the current one",
+            ),
+            (HeapError::Incomparable, "keys are not comparable (NaN?)"),
+            (HeapError::CapacityExceeded, "heap is at its maximum length"),
+            (
+                HeapError::StaleHandle,
+                "handle refers to an entry that is gone",
+            ),
+            (
+                HeapError::InvalidLayout("positions too short"),
+                "invalid heap layout: positions too short",
+            ),
+        ];
+        for (err, msg) in cases {
+            assert_eq!(err.to_string(), msg);
+        }
+    }
+
+    #[test]
+    fn boxes_as_dyn_error() {
+        fn run() -> Result<(), Box<dyn Error>> {
+            let mut mh = MinHeap::with_max_len(1);
+            mh.try_insert((0, 1.0))?;
+            mh.try_insert((1, 2.0))?;
+            Ok(())
+        }
+        let err = run().unwrap_err();
+        assert_eq!(err.to_string(), "heap is at its maximum length");
+        assert_eq!(
+            err.downcast_ref::<HeapError>(),
+            Some(&HeapError::CapacityExceeded)
+        );
+        assert!(err.source().is_none());
+    }
+}
@@ -1,9 +1,24 @@
 //! Fibonacci heap with `(id, key)` API identical to `MinHeap`.
+//! Keys only need `PartialOrd + Clone`; they are cloned when popped.
 //! Correct for all decrease-key / clear / multi-phase workloads.
 
 use std::cmp::Ordering;
+use std::fmt;
+
+use crate::ids::IdAllocator;
+use crate::metrics::Counter;
+#[cfg(feature = "metrics")]
+use crate::metrics::OpCounters;
+#[cfg(feature = "bounded")]
+use crate::Bounded;
+use crate::{HeapError, Op, OpResult};
+use std::ops::Sub;
 const NOT_IN_HEAP: usize = usize::MAX;
 
+/// Callback for `FibHeap::set_cut_observer`: `(node_id, parent_id, was_marked)`.
+/// `Send + Sync` so the heap keeps both auto traits.
+type CutObserver = Box<dyn FnMut(usize, usize, bool) + Send + Sync>;
+
 #[derive(Clone)]
 struct Node<K> {
     entry: (usize, K),
@@ -15,7 +30,7 @@ struct Node<K> {
     right: usize,
 }
 
-impl<K: PartialOrd + Copy> Node<K> {
+impl<K: PartialOrd + Clone> Node<K> {
     fn new(id: usize, key: K, idx: usize) -> Self {
         Self {
             entry: (id, key),
@@ -29,16 +44,170 @@ impl<K: PartialOrd + Copy> Node<K> {
     }
 }
 
+/// When `delete_min` consolidates the root list.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConsolidatePolicy {
+    /// Consolidate on every `delete_min` (the classic algorithm).
+    #[default]
+    Eager,
+    /// Only consolidate once the root list is longer than
+    /// `factor * log2(n)`; otherwise just rescan the roots for the minimum.
+    LazyThreshold(f64),
+}
+
 pub struct FibHeap<K> {
     nodes: Vec<Node<K>>,
     positions: Vec<usize>, // id → node index | NOT_IN_HEAP
     min_root: Option<usize>,
     n: usize,
+    roots: usize, // length of the root ring
+    comparisons: Counter,
+    consolidations: Counter,
+    links: Counter,
+    cuts: Counter,
+    cascading_cuts: Counter,
+    scratch_grows: Counter, // scratch buffer reallocations
+    policy: ConsolidatePolicy,
+    gc_threshold: f64, // compact `nodes` once dead / total exceeds this
+    stable: bool,      // break key ties by id
+    cut_observer: Option<CutObserver>,
     scratch_roots: Vec<usize>,
     scratch_aux: Vec<Option<usize>>,
+    ids: IdAllocator, // ids handed out by `push`
+}
+
+impl<K: Clone> Clone for FibHeap<K> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            positions: self.positions.clone(),
+            min_root: self.min_root,
+            n: self.n,
+            roots: self.roots,
+            comparisons: self.comparisons.clone(),
+            consolidations: self.consolidations.clone(),
+            links: self.links.clone(),
+            cuts: self.cuts.clone(),
+            cascading_cuts: self.cascading_cuts.clone(),
+            scratch_grows: self.scratch_grows.clone(),
+            policy: self.policy,
+            gc_threshold: self.gc_threshold,
+            stable: self.stable,
+            cut_observer: None,
+            scratch_roots: Vec::new(),
+            scratch_aux: Vec::new(),
+            ids: self.ids.clone(),
+        }
+    }
+
+    /// Reuses `self`'s allocations (including the scratch buffers).
+    fn clone_from(&mut self, source: &Self) {
+        self.nodes.clone_from(&source.nodes);
+        self.positions.clone_from(&source.positions);
+        self.min_root = source.min_root;
+        self.n = source.n;
+        self.roots = source.roots;
+        self.policy = source.policy;
+        self.gc_threshold = source.gc_threshold;
+        self.stable = source.stable;
+        self.ids.clone_from(&source.ids);
+    }
+}
+
+/// Equal when both hold the same live `(id, key)` entries, regardless of
+/// forest shape. Compares id-sorted copies, O(n log n).
+impl<K: PartialEq> PartialEq for FibHeap<K> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.n != other.n {
+            return false;
+        }
+        fn sorted<K>(h: &FibHeap<K>) -> Vec<&(usize, K)> {
+            let mut entries: Vec<_> = h
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(idx, node)| h.positions.get(node.entry.0) == Some(idx))
+                .map(|(_, node)| &node.entry)
+                .collect();
+            entries.sort_unstable_by_key(|e| e.0);
+            entries
+        }
+        sorted(self) == sorted(other)
+    }
+}
+
+/// Inserts each item; the root ring just grows, nothing is consolidated
+/// until the first `delete_min`.
+impl<K: PartialOrd + Clone> FromIterator<(usize, K)> for FibHeap<K> {
+    fn from_iter<I: IntoIterator<Item = (usize, K)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut h = Self::new();
+        let (lower, _) = iter.size_hint();
+        h.nodes.reserve(lower);
+        h.positions.reserve(lower);
+        h.extend(iter);
+        h
+    }
+}
+
+impl<K: PartialOrd + Clone> Extend<(usize, K)> for FibHeap<K> {
+    fn extend<I: IntoIterator<Item = (usize, K)>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+/// Borrowing iterator over the live entries, see `FibHeap::iter`.
+pub struct FibHeapIter<'a, K> {
+    heap: &'a FibHeap<K>,
+    // (first slot, next slot) of every ring still being walked
+    rings: Vec<(usize, usize)>,
+    remaining: usize,
+}
+
+impl<'a, K> Iterator for FibHeapIter<'a, K> {
+    type Item = &'a (usize, K);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes = &self.heap.nodes;
+        let (start, cur) = self.rings.last_mut()?;
+        let idx = *cur;
+        if nodes[idx].right == *start {
+            self.rings.pop();
+        } else {
+            *cur = nodes[idx].right;
+        }
+        if let Some(c) = nodes[idx].child {
+            self.rings.push((c, c));
+        }
+        self.remaining -= 1;
+        Some(&nodes[idx].entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K> ExactSizeIterator for FibHeapIter<'_, K> {}
+
+impl<'a, K: PartialOrd + Clone> IntoIterator for &'a FibHeap<K> {
+    type Item = &'a (usize, K);
+    type IntoIter = FibHeapIter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: PartialOrd + Clone> Default for FibHeap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<K: PartialOrd + Copy> FibHeap<K> {
+impl<K: PartialOrd + Clone> FibHeap<K> {
     /* ---------- public API (matches MinHeap) ----------------------------- */
     pub fn new() -> Self {
         Self {
@@ -46,10 +215,34 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
             positions: Vec::new(),
             min_root: None,
             n: 0,
+            roots: 0,
+            comparisons: Counter::default(),
+            consolidations: Counter::default(),
+            links: Counter::default(),
+            cuts: Counter::default(),
+            cascading_cuts: Counter::default(),
+            scratch_grows: Counter::default(),
+            policy: ConsolidatePolicy::Eager,
+            gc_threshold: 0.5,
+            stable: false,
+            cut_observer: None,
             scratch_roots: Vec::new(),
             scratch_aux: Vec::new(),
+            ids: IdAllocator::default(),
         }
     }
+
+    /// Empty heap whose consolidation buffers are sized up front, so the
+    /// first `delete_min` doesn't allocate: `max_roots_hint` bounds the root
+    /// list (up to `n` after `n` inserts) and `max_degree_hint` the tree
+    /// degree, `ceil(log2(n)) + 2` for `n` items.
+    pub fn with_scratch_capacity(max_degree_hint: usize, max_roots_hint: usize) -> Self {
+        let mut h = Self::new();
+        h.scratch_roots.reserve_exact(max_roots_hint);
+        h.scratch_aux.resize(max_degree_hint, None);
+        h
+    }
+
     pub fn is_empty(&self) -> bool {
         self.n == 0
     }
@@ -57,13 +250,90 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         self.n
     }
 
+    /// Number of trees in the root list, maintained in O(1). A long root
+    /// list means the next `delete_min` has a lot of consolidating to do.
+    pub fn root_count(&self) -> usize {
+        self.roots
+    }
+
+    /// Allocated length of the id-indexed `positions` table. It grows with
+    /// the largest id ever inserted, not with the number of live nodes.
+    pub fn positions_capacity(&self) -> usize {
+        self.positions.capacity()
+    }
+
+    /// Drop trailing unused entries of `positions` and release the memory.
+    /// Never shrinks below the largest live id.
+    pub fn shrink_positions(&mut self) {
+        let keep = self
+            .positions
+            .iter()
+            .rposition(|&p| p != NOT_IN_HEAP)
+            .map_or(0, |i| i + 1);
+        self.positions.truncate(keep);
+        self.positions.shrink_to_fit();
+    }
+
+    /// Pay the pending consolidation now instead of at the next
+    /// `delete_min`, e.g. during an idle moment after a burst of inserts.
+    /// No-op with fewer than two roots.
+    pub fn force_consolidate(&mut self) {
+        if self.roots > 1 {
+            self.consolidate();
+        }
+    }
+
+    /// Consolidate now, calling `on_link(child_id, parent_id)` for every
+    /// link in the order they happen, e.g. to animate the merging. The
+    /// resulting forest is the one `force_consolidate` would build.
+    pub fn consolidate_traced(&mut self, on_link: impl FnMut(usize, usize)) {
+        self.consolidate_with(on_link);
+    }
+
+    pub fn set_consolidate_policy(&mut self, policy: ConsolidatePolicy) {
+        self.policy = policy;
+    }
+
+    /// Popped nodes leave dead slots behind in the node arena. Once more
+    /// than `ratio` of all slots are dead, `delete_min` (and `delete`)
+    /// compact the arena in place, O(slots), keeping the forest shape.
+    /// Defaults to 0.5; any ratio >= 1.0 turns the collection off.
+    pub fn set_gc_threshold(&mut self, ratio: f64) {
+        self.gc_threshold = ratio;
+    }
+
+    /// Break key ties by id everywhere two nodes are compared (linking,
+    /// picking the min root, cutting), so equal keys pop in ascending id
+    /// order regardless of insertion order. Set it while the heap is empty.
+    pub fn set_stable(&mut self, stable: bool) {
+        debug_assert!(self.is_empty(), "set_stable on a non-empty heap");
+        self.stable = stable;
+    }
+
+    /// Call `f(node_id, parent_id, was_marked)` every time a node is cut
+    /// from its parent, both for the initial cut in `decrease_key` and for
+    /// each step of the cascade (those always report `was_marked == true`).
+    /// Unset, a cut costs one `None` check. Clones don't get the observer.
+    pub fn set_cut_observer(&mut self, f: impl FnMut(usize, usize, bool) + Send + Sync + 'static) {
+        self.cut_observer = Some(Box::new(f));
+    }
+
+    pub fn clear_cut_observer(&mut self) {
+        self.cut_observer = None;
+    }
+
     pub fn clear(&mut self) {
         for node in &self.nodes {
-            self.positions[node.entry.0] = NOT_IN_HEAP;
+            // dead slots may hold ids beyond a shrunk positions table
+            if let Some(p) = self.positions.get_mut(node.entry.0) {
+                *p = NOT_IN_HEAP;
+            }
         }
         self.nodes.clear();
         self.min_root = None;
         self.n = 0;
+        self.roots = 0;
+        self.ids.reset();
     }
 
     pub fn build_heap(items: Vec<(usize, K)>) -> Self {
@@ -74,7 +344,18 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         h
     }
 
+    /// Insert under the next free id and return it, for callers that don't
+    /// manage ids. Ids that left the heap are reused before new ones.
+    pub fn push(&mut self, key: K) -> usize {
+        let mut ids = std::mem::take(&mut self.ids);
+        let id = ids.next(|id| self.contains(id));
+        self.ids = ids;
+        self.insert((id, key));
+        id
+    }
+
     pub fn insert(&mut self, (id, key): (usize, K)) {
+        assert!(id != NOT_IN_HEAP, "id too large: {id}");
         debug_assert!(
             id >= self.positions.len() || self.positions[id] == NOT_IN_HEAP,
             "duplicate id {} inserted",
@@ -94,38 +375,138 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         self.update_min(idx);
     }
 
+    /// Insert every item and return the resulting minimum. `positions` and
+    /// the node arena grow once up front; each item costs O(1) as usual.
+    pub fn insert_batch(&mut self, items: &[(usize, K)]) -> Option<(usize, K)> {
+        if let Some(max_id) = items.iter().map(|e| e.0).max() {
+            assert!(max_id != NOT_IN_HEAP, "id too large: {max_id}");
+            if max_id >= self.positions.len() {
+                self.positions.resize(max_id + 1, NOT_IN_HEAP);
+            }
+        }
+        self.nodes.reserve(items.len());
+        for item in items {
+            self.insert(item.clone());
+        }
+        self.peek()
+    }
+
     pub fn get_min(&self) -> Option<&(usize, K)> {
         self.min_root.map(|i| &self.nodes[i].entry)
     }
 
+    pub fn peek(&self) -> Option<(usize, K)> {
+        self.get_min().cloned()
+    }
+
+    /// Read the minimum by reference instead of cloning its key.
+    pub fn with_min<R>(&self, f: impl FnOnce(usize, &K) -> R) -> Option<R> {
+        self.get_min().map(|(id, key)| f(*id, key))
+    }
+
+    /// Run `f` on the minimum while it is still in the heap, then pop it.
+    pub fn pop_and<R>(&mut self, f: impl FnOnce(usize, &K) -> R) -> Option<R> {
+        let out = self.with_min(f)?;
+        self.delete_min();
+        Some(out)
+    }
+
+    pub fn get_min_id(&self) -> Option<usize> {
+        self.min_root.map(|i| self.nodes[i].entry.0)
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.positions
+            .get(id)
+            .is_some_and(|&idx| idx != NOT_IN_HEAP)
+    }
+
+    /// Remove `id` wherever it sits: cut it (with the usual cascade) to the
+    /// root list, make it the min and pop it. Same amortized cost as
+    /// `delete_min`. `None` if `id` isn't in the heap.
+    pub fn delete(&mut self, id: usize) -> Option<(usize, K)> {
+        if !self.contains(id) {
+            return None;
+        }
+        let idx = self.positions[id];
+        if let Some(p) = self.nodes[idx].parent {
+            self.cut(idx, p);
+            self.cascading_cut(p);
+        }
+        self.min_root = Some(idx);
+        self.delete_min()
+    }
+
+    /// Run one [`Op`]. Invalid ops (duplicate or missing id, a key that
+    /// doesn't decrease, a NaN) return `OpResult::Failed` and change nothing.
+    pub fn apply(&mut self, op: Op<K>) -> OpResult<K> {
+        match op {
+            Op::Insert(id, key) => {
+                if id == NOT_IN_HEAP {
+                    return OpResult::Failed(HeapError::IdTooLarge(id));
+                }
+                if self.contains(id) {
+                    return OpResult::Failed(HeapError::DuplicateId(id));
+                }
+                if key.partial_cmp(&key).is_none() {
+                    return OpResult::Failed(HeapError::Incomparable);
+                }
+                self.insert((id, key));
+                OpResult::Done
+            }
+            Op::DeleteMin => OpResult::Popped(self.delete_min()),
+            Op::DecreaseKey(id, key) => match self.try_decrease_key(id, key) {
+                Ok(()) => OpResult::Done,
+                Err(e) => OpResult::Failed(e),
+            },
+            Op::Delete(id) => match self.delete(id) {
+                Some(item) => OpResult::Deleted(item),
+                None => OpResult::Failed(HeapError::NotPresent(id)),
+            },
+            Op::Clear => {
+                self.clear();
+                OpResult::Done
+            }
+        }
+    }
+
+    /// Number of live nodes that have lost a child since they were last
+    /// linked, i.e. how many cuts are one decrease away from cascading.
+    /// O(n) scan.
+    pub fn marked_count(&self) -> usize {
+        (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].mark && self.is_live(i))
+            .count()
+    }
+
+    /// Insert and return the (possibly new) minimum.
+    pub fn insert_observing(&mut self, item: (usize, K)) -> Option<(usize, K)> {
+        self.insert(item);
+        self.peek()
+    }
+
     pub fn delete_min(&mut self) -> Option<(usize, K)> {
         /* 0) empty heap? */
         let z = self.min_root?; // return None if empty
 
         /* 1) promote every child of z to the root list */
-        if let Some(mut child) = self.nodes[z].child {
-            loop {
-                let next = self.nodes[child].right; // save before detach
-                self.detach(child); // unlink from child list
-                self.nodes[child].parent = None;
-                self.nodes[child].mark = false;
-                self.add_to_root(child); // add to root ring
-                if next == child {
-                    break;
-                } // finished full circle
-                child = next;
-            }
-            self.nodes[z].child = None;
-        }
+        self.promote_children(z);
+
+        self.unlink_min_root(z)
+    }
 
+    /// The rest of `delete_min` once the children of the min root `z` sit in
+    /// the root list.
+    fn unlink_min_root(&mut self, z: usize) -> Option<(usize, K)> {
         /* 2) remove z itself from the root list */
         let successor = self.nodes[z].right; // neighbour root
         self.detach(z);
 
         /* 3) book-keeping for the item we return */
         self.n -= 1;
-        let (id, key) = self.nodes[z].entry;
+        let (id, key) = self.nodes[z].entry.clone();
         self.positions[id] = NOT_IN_HEAP;
+        self.ids.release(id);
 
         /* 4) choose a new min root and consolidate */
         if self.n == 0 {
@@ -135,15 +516,76 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
             if self.min_root == Some(z) {
                 self.min_root = Some(successor);
             }
-            self.consolidate(); // rebuild and set true min
+            /* a single remaining tree is already consolidated */
+            if self.roots > 1 {
+                if self.wants_consolidate() {
+                    self.consolidate(); // rebuild and set true min
+                } else {
+                    self.rescan_min(); // lazy: only find the true min
+                }
+            }
+        }
+
+        /* 5) collect dead slots once they dominate the arena */
+        let dead = self.nodes.len() - self.n;
+        if dead as f64 > self.gc_threshold * self.nodes.len() as f64 {
+            self.compact_nodes();
         }
 
         Some((id, key))
     }
 
+    /// `delete_min` that reports `Incomparable` instead of letting a NaN
+    /// skew consolidation into picking the wrong min. Consolidation only
+    /// compares the other roots and the min's children, so those keys are
+    /// checked (against themselves and the running minimum) before anything
+    /// is unlinked; on error nothing has changed.
+    pub fn try_delete_min(&mut self) -> Result<Option<(usize, K)>, HeapError> {
+        let Some(z) = self.min_root else {
+            return Ok(None);
+        };
+        let mut slots = self.ring_slots(z);
+        if let Some(c) = self.nodes[z].child {
+            slots.extend(self.ring_slots(c));
+        }
+        let mut best: Option<&K> = None;
+        for slot in slots.into_iter().filter(|&s| s != z) {
+            let key = &self.nodes[slot].entry.1;
+            if key.partial_cmp(key).is_none() {
+                return Err(HeapError::Incomparable);
+            }
+            match best.map(|b| key.partial_cmp(b)) {
+                None | Some(Some(Ordering::Less)) => best = Some(key),
+                Some(Some(_)) => {}
+                Some(None) => return Err(HeapError::Incomparable),
+            }
+        }
+        Ok(self.delete_min())
+    }
+
+    /// `decrease_key` that checks its input instead of asserting: the id must
+    /// be live and the new key strictly smaller (a NaN is `Incomparable`).
+    /// Nothing is modified on error.
+    pub fn try_decrease_key(&mut self, id: usize, new_key: K) -> Result<(), HeapError> {
+        let idx = match self.positions.get(id) {
+            Some(&idx) if idx != NOT_IN_HEAP => idx,
+            _ => return Err(HeapError::NotPresent(id)),
+        };
+        match new_key.partial_cmp(&self.nodes[idx].entry.1) {
+            Some(Ordering::Less) => {}
+            Some(_) => return Err(HeapError::KeyNotSmaller(id)),
+            None => return Err(HeapError::Incomparable),
+        }
+        self.decrease_key(id, new_key);
+        Ok(())
+    }
+
+    /// Does nothing if `id` isn't in the heap.
     pub fn decrease_key(&mut self, id: usize, new_key: K) {
-        // get the node index more directly
-        let idx = self.positions[id];
+        let idx = match self.positions.get(id) {
+            Some(&idx) if idx != NOT_IN_HEAP => idx,
+            _ => return,
+        };
         // one fewer method call vs. partial_cmp+unwrap
         debug_assert!(self.nodes[idx].entry.1 > new_key, "new key must be smaller");
 
@@ -152,7 +594,7 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
 
         // only if it has a parent—and its key is now smaller—cut & cascade
         if let Some(p) = self.nodes[idx].parent {
-            if self.nodes[idx].entry.1 < self.nodes[p].entry.1 {
+            if self.less(idx, p) {
                 self.cut(idx, p);
                 self.cascading_cut(p);
             }
@@ -160,19 +602,327 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         self.update_min(idx);
     }
 
+    /// Every live entry exactly once, walking the forest depth-first from
+    /// the min root. The order is unspecified; nothing is modified.
+    pub fn iter(&self) -> FibHeapIter<'_, K> {
+        FibHeapIter {
+            heap: self,
+            rings: self.min_root.map(|r| (r, r)).into_iter().collect(),
+            remaining: self.n,
+        }
+    }
+
+    /// Copy of the live entries in internal (slot) order.
+    pub fn to_vec(&self) -> Vec<(usize, K)> {
+        let mut out = Vec::with_capacity(self.n);
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if self.is_live(idx) {
+                out.push(node.entry.clone());
+            }
+        }
+        out
+    }
+
+    /// Consume the heap and return its entries in ascending key order.
+    pub fn into_sorted_vec(mut self) -> Vec<(usize, K)> {
+        let mut out = Vec::with_capacity(self.n);
+        while let Some(item) = self.delete_min() {
+            out.push(item);
+        }
+        out
+    }
+
+    /* ---------- melding ------------------------------------------------- */
+
+    /// Move every entry of `other` into `self`. Ids must be disjoint;
+    /// on a collision nothing is moved. The forests are spliced together
+    /// without any linking, the only linear part is copying `other`'s arena.
+    pub fn union(&mut self, other: FibHeap<K>) -> Result<(), HeapError> {
+        for (slot, node) in other.nodes.iter().enumerate() {
+            let id = node.entry.0;
+            if other.is_live(slot) && self.positions.get(id).is_some_and(|&p| p != NOT_IN_HEAP) {
+                return Err(HeapError::DuplicateId(id));
+            }
+        }
+        self.meld(other, 0);
+        Ok(())
+    }
+
+    /// Union for heaps whose ids overlap: `other`'s ids are shifted past
+    /// `self`'s largest live id. Returns the `(old, new)` id pairs, sorted by
+    /// old id, so callers can fix up external references.
+    pub fn union_remap(&mut self, other: FibHeap<K>) -> Vec<(usize, usize)> {
+        let offset = self
+            .positions
+            .iter()
+            .rposition(|&p| p != NOT_IN_HEAP)
+            .map_or(0, |max_id| max_id + 1);
+        let mut mapping: Vec<_> = (0..other.nodes.len())
+            .filter(|&slot| other.is_live(slot))
+            .map(|slot| {
+                let id = other.nodes[slot].entry.0;
+                (id, id + offset)
+            })
+            .collect();
+        mapping.sort_unstable();
+        self.meld(other, offset);
+        mapping
+    }
+
+    /// Append `other`'s arena (ids shifted by `id_offset`) and splice its
+    /// root ring into ours.
+    fn meld(&mut self, other: FibHeap<K>, id_offset: usize) {
+        let base = self.nodes.len();
+        let other_min = match other.min_root {
+            Some(m) => m + base,
+            None => return,
+        };
+
+        let live: Vec<bool> = (0..other.nodes.len()).map(|i| other.is_live(i)).collect();
+        for (node, live) in other.nodes.into_iter().zip(live) {
+            let mut node = node;
+            node.entry.0 += id_offset;
+            node.parent = node.parent.map(|p| p + base);
+            node.child = node.child.map(|c| c + base);
+            node.left += base;
+            node.right += base;
+
+            let slot = self.nodes.len();
+            if live {
+                let id = node.entry.0;
+                if id >= self.positions.len() {
+                    self.positions.resize(id + 1, NOT_IN_HEAP);
+                }
+                self.positions[id] = slot;
+            }
+            self.nodes.push(node);
+        }
+
+        match self.min_root {
+            None => self.min_root = Some(other_min),
+            Some(m) => {
+                // splice the two rings: m → other_min … other_last → m_right
+                let m_right = self.nodes[m].right;
+                let other_last = self.nodes[other_min].left;
+                self.nodes[m].right = other_min;
+                self.nodes[other_min].left = m;
+                self.nodes[other_last].right = m_right;
+                self.nodes[m_right].left = other_last;
+                if self.less(other_min, m) {
+                    self.min_root = Some(other_min);
+                }
+            }
+        }
+        self.n += other.n;
+        self.roots += other.roots;
+    }
+
+    /* ---------- structure inspection ----------------------------------- */
+
+    /// Ids of the root list, starting at the current minimum.
+    pub fn roots(&self) -> Vec<usize> {
+        match self.min_root {
+            Some(r) => self.ring_ids(r),
+            None => Vec::new(),
+        }
+    }
+
+    /// Ids of the direct children of `id`; empty if `id` is not in the heap.
+    pub fn children_of(&self, id: usize) -> Vec<usize> {
+        match self.positions.get(id) {
+            Some(&idx) if idx != NOT_IN_HEAP => match self.nodes[idx].child {
+                Some(c) => self.ring_ids(c),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Walk a sibling ring and collect ids; capped at `n` steps so a broken
+    /// ring can't loop forever.
+    fn ring_ids(&self, start: usize) -> Vec<usize> {
+        self.ring_slots(start)
+            .into_iter()
+            .map(|i| self.nodes[i].entry.0)
+            .collect()
+    }
+
+    fn ring_slots(&self, start: usize) -> Vec<usize> {
+        let mut slots = Vec::new();
+        let mut cur = start;
+        for _ in 0..self.n {
+            slots.push(cur);
+            cur = self.nodes[cur].right;
+            if cur == start {
+                break;
+            }
+        }
+        slots
+    }
+
+    /// Make a corrupted heap valid again: every reachable node whose
+    /// `positions` entry points back at it is kept, the forest is dropped and
+    /// the kept entries are re-inserted.
+    pub fn rebuild(&mut self) {
+        let entries: Vec<_> = self
+            .reachable_slots()
+            .into_iter()
+            .filter(|&slot| self.is_live(slot))
+            .map(|slot| self.nodes[slot].entry.clone())
+            .collect();
+
+        self.positions.fill(NOT_IN_HEAP);
+        self.nodes.clear();
+        self.min_root = None;
+        self.n = 0;
+        self.roots = 0;
+        for entry in entries {
+            self.insert(entry);
+        }
+    }
+
+    /// Panics unless the forest is consistent: every reachable node is
+    /// mapped by `positions`, nothing else is, parents are ≤ children,
+    /// `min_root` is the smallest root and the counters match.
+    pub fn assert_valid(&self) {
+        let reachable = self.reachable_slots();
+        assert_eq!(reachable.len(), self.n, "reachable nodes != len()");
+        for &i in &reachable {
+            let id = self.nodes[i].entry.0;
+            assert_eq!(
+                self.positions.get(id),
+                Some(&i),
+                "positions[{id}] doesn't point at its node"
+            );
+            if let Some(p) = self.nodes[i].parent {
+                assert!(
+                    self.nodes[p].entry.1.partial_cmp(&self.nodes[i].entry.1)
+                        != Some(Ordering::Greater),
+                    "heap order violated below id {}",
+                    self.nodes[p].entry.0
+                );
+            }
+        }
+        let live = self.positions.iter().filter(|&&p| p != NOT_IN_HEAP).count();
+        assert_eq!(live, self.n, "positions has stale entries");
+
+        if let Some(m) = self.min_root {
+            let ring = self.ring_slots(m);
+            assert_eq!(ring.len(), self.roots, "root counter out of sync");
+            for &r in &ring {
+                assert!(
+                    self.nodes[m].entry.1.partial_cmp(&self.nodes[r].entry.1)
+                        != Some(Ordering::Greater),
+                    "min_root is not the smallest root"
+                );
+            }
+        }
+    }
+
+    /// Every slot reachable from the root list, each once; stops at
+    /// already-visited slots so broken rings can't loop.
+    fn reachable_slots(&self) -> Vec<usize> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut out = Vec::new();
+        let mut rings: Vec<usize> = self.min_root.into_iter().collect();
+        while let Some(start) = rings.pop() {
+            let mut cur = start;
+            while !seen[cur] {
+                seen[cur] = true;
+                out.push(cur);
+                if let Some(c) = self.nodes[cur].child {
+                    rings.push(c);
+                }
+                cur = self.nodes[cur].right;
+            }
+        }
+        out
+    }
+
     /* ---------- helpers -------------------------------------------------- */
 
+    /// Force a node's mark, to set up a specific cascade in tests.
+    #[cfg(test)]
+    pub(crate) fn set_mark_for_test(&mut self, id: usize, mark: bool) {
+        let idx = self.positions[id];
+        self.nodes[idx].mark = mark;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_marked(&self, id: usize) -> bool {
+        self.nodes[self.positions[id]].mark
+    }
+
+    /// Slide the live nodes to the front of `nodes` (keeping their relative
+    /// order), drop the dead tail and rewrite every slot reference. The
+    /// forest itself is untouched, so no re-linking happens.
+    fn compact_nodes(&mut self) {
+        let mut remap = vec![NOT_IN_HEAP; self.nodes.len()];
+        let mut w = 0;
+        for (i, slot) in remap.iter_mut().enumerate() {
+            if self.is_live(i) {
+                *slot = w;
+                self.nodes.swap(w, i);
+                w += 1;
+            }
+        }
+        self.nodes.truncate(w);
+
+        for node in &mut self.nodes {
+            node.left = remap[node.left];
+            node.right = remap[node.right];
+            node.parent = node.parent.map(|p| remap[p]);
+            node.child = node.child.map(|c| remap[c]);
+            self.positions[node.entry.0] = remap[self.positions[node.entry.0]];
+        }
+        self.min_root = self.min_root.map(|m| remap[m]);
+    }
+
+    fn wants_consolidate(&self) -> bool {
+        match self.policy {
+            ConsolidatePolicy::Eager => true,
+            ConsolidatePolicy::LazyThreshold(factor) => {
+                self.roots as f64 > factor * (self.n as f64).log2()
+            }
+        }
+    }
+
+    /// Point `min_root` at the smallest root without restructuring.
+    fn rescan_min(&mut self) {
+        if let Some(start) = self.min_root {
+            let mut best = start;
+            let mut cur = self.nodes[start].right;
+            while cur != start {
+                if self.less(cur, best) {
+                    best = cur;
+                }
+                cur = self.nodes[cur].right;
+            }
+            self.min_root = Some(best);
+        }
+    }
+
+    /// Dead slots stay in `nodes`; a slot is live iff its id still maps to it.
+    fn is_live(&self, idx: usize) -> bool {
+        self.positions.get(self.nodes[idx].entry.0) == Some(&idx)
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.comparisons.bump();
+        let (id_a, key_a) = &self.nodes[a].entry;
+        let (id_b, key_b) = &self.nodes[b].entry;
+        match key_a.partial_cmp(key_b) {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => self.stable && id_a < id_b,
+            _ => false,
+        }
+    }
+
     fn update_min(&mut self, idx: usize) {
         match self.min_root {
             None => self.min_root = Some(idx),
             Some(m) => {
-                if self.nodes[idx]
-                    .entry
-                    .1
-                    .partial_cmp(&self.nodes[m].entry.1)
-                    .unwrap()
-                    == Ordering::Less
-                {
+                if self.less(idx, m) {
                     self.min_root = Some(idx);
                 }
             }
@@ -190,27 +940,104 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
 
             /* ---------- NEW ---------- */
             // keep the pointer on the smallest key
-            if self.nodes[idx].entry.1 < self.nodes[min_idx].entry.1 {
+            if self.less(idx, min_idx) {
                 self.min_root = Some(idx);
             }
             /* -------------------------- */
         } else {
             self.min_root = Some(idx); // first root in the ring
         }
+        self.roots += 1;
     }
 
-    fn detach(&mut self, i: usize) {
-        let l = self.nodes[i].left;
-        let r = self.nodes[i].right;
-        self.nodes[l].right = r;
-        self.nodes[r].left = l;
-        self.nodes[i].left = i;
-        self.nodes[i].right = i;
-    }
+    /// Move all children of `z` to the root list. One pass over the child
+    /// ring clears parents and marks (collecting the ids in `scratch_roots`),
+    /// then the ring is spliced in whole before the min: four pointer writes
+    /// instead of a detach and `add_to_root` per child.
+    fn promote_children(&mut self, z: usize) {
+        let first = match self.nodes[z].child.take() {
+            Some(c) => c,
+            None => return,
+        };
+        let mut children = std::mem::take(&mut self.scratch_roots);
+        children.clear();
+        let cap = children.capacity();
+        let mut c = first;
+        loop {
+            children.push(c);
+            self.nodes[c].parent = None;
+            self.nodes[c].mark = false;
+            c = self.nodes[c].right;
+            if c == first {
+                break;
+            }
+        }
 
-    fn link(&mut self, y: usize, x: usize) {
-        /* y becomes child of x ( keys[x] <= keys[y] ) */
-        self.detach(y);
+        // z is a root, so the root ring is not empty
+        let min_idx = self.min_root.expect("z is in the root list");
+        let last = self.nodes[first].left;
+        let left = self.nodes[min_idx].left;
+        self.nodes[left].right = first;
+        self.nodes[first].left = left;
+        self.nodes[last].right = min_idx;
+        self.nodes[min_idx].left = last;
+        self.roots += children.len();
+
+        // same pick as add_to_root one child at a time: first strict minimum
+        for &c in &children {
+            if self.less(c, self.min_root.unwrap()) {
+                self.min_root = Some(c);
+            }
+        }
+        if children.capacity() > cap {
+            self.scratch_grows.bump();
+        }
+        self.scratch_roots = children;
+    }
+
+    /// `delete_min` with the per-child promotion `promote_children`
+    /// replaced, kept as a reference for tests.
+    #[cfg(test)]
+    fn delete_min_per_child(&mut self) -> Option<(usize, K)> {
+        let z = self.min_root?;
+        self.promote_children_per_child(z);
+        self.unlink_min_root(z)
+    }
+
+    #[cfg(test)]
+    fn promote_children_per_child(&mut self, z: usize) {
+        if let Some(mut child) = self.nodes[z].child {
+            loop {
+                let next = self.nodes[child].right; // save before detach
+                self.detach(child); // unlink from child list
+                self.nodes[child].parent = None;
+                self.nodes[child].mark = false;
+                self.add_to_root(child); // add to root ring
+                if next == child {
+                    break;
+                } // finished full circle
+                child = next;
+            }
+            self.nodes[z].child = None;
+        }
+    }
+
+    fn detach(&mut self, i: usize) {
+        if self.nodes[i].parent.is_none() {
+            self.roots -= 1;
+        }
+        let l = self.nodes[i].left;
+        let r = self.nodes[i].right;
+        self.nodes[l].right = r;
+        self.nodes[r].left = l;
+        self.nodes[i].left = i;
+        self.nodes[i].right = i;
+    }
+
+    fn link(&mut self, y: usize, x: usize) {
+        /* y becomes child of x ( keys[x] <= keys[y] ) */
+        self.links.bump();
+        self.detach(y);
 
         if let Some(c) = self.nodes[x].child {
             // splice y before c in child list
@@ -228,6 +1055,14 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
     }
 
     fn cut(&mut self, idx: usize, parent: usize) {
+        self.cuts.bump();
+        if let Some(f) = self.cut_observer.as_mut() {
+            f(
+                self.nodes[idx].entry.0,
+                self.nodes[parent].entry.0,
+                self.nodes[idx].mark,
+            );
+        }
         /* unlink idx from parent.child list */
         if self.nodes[parent].child == Some(idx) {
             if self.nodes[idx].right == idx {
@@ -254,6 +1089,7 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
                 self.nodes[y].mark = true;
                 break;
             }
+            self.cascading_cuts.bump();
             self.cut(y, p);
             y = p;
         }
@@ -264,15 +1100,23 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
     /// Consolidate the root list: combine trees of equal degree until each
     /// degree occurs at most once.
     fn consolidate(&mut self) {
+        self.consolidate_with(|_, _| {});
+    }
+
+    /// `consolidate` reporting each link as `(child_id, parent_id)`. Generic
+    /// so the untraced call compiles the hook away.
+    fn consolidate_with(&mut self, mut on_link: impl FnMut(usize, usize)) {
         // early exit
         let start = match self.min_root {
             Some(i) => i,
             None => return,
         };
+        self.consolidations.bump();
 
         // ── 1) take the pre-allocated roots Vec out, clear it, fill it ──
         let mut roots = std::mem::take(&mut self.scratch_roots);
         roots.clear();
+        let caps = (roots.capacity(), self.scratch_aux.capacity());
         let mut w = start;
         loop {
             roots.push(w);
@@ -309,11 +1153,12 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
                     break;
                 }
                 let mut y = self.scratch_aux[d].take().unwrap();
-                if self.nodes[y].entry.1 < self.nodes[x].entry.1 {
+                if self.less(y, x) {
                     std::mem::swap(&mut x, &mut y);
                 }
                 // this borrows &mut self, but no scratch_roots borrow is active
                 self.link(y, x);
+                on_link(self.nodes[y].entry.0, self.nodes[x].entry.0);
                 d += 1;
             }
         }
@@ -321,6 +1166,7 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         // ── 4) rebuild the root ring by taking scratch_aux out ──
         let aux = std::mem::take(&mut self.scratch_aux);
         self.min_root = None;
+        self.roots = 0;
         for opt in aux.iter() {
             if let Some(idx) = *opt {
                 self.nodes[idx].left = idx;
@@ -332,6 +1178,9 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
         }
 
         // put our buffers back for the next call
+        if roots.capacity() > caps.0 || aux.capacity() > caps.1 {
+            self.scratch_grows.bump();
+        }
         self.scratch_aux = aux;
         self.scratch_roots = roots;
 
@@ -351,11 +1200,12 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
                     break;
                 }
             }
-            assert_eq!(seen.len(), self.root_count(), "root list size wrong");
+            assert_eq!(seen.len(), self.walk_root_count(), "root list size wrong");
+            assert_eq!(seen.len(), self.roots, "root counter out of sync");
         }
     }
     #[cfg(debug_assertions)]
-    fn root_count(&self) -> usize {
+    fn walk_root_count(&self) -> usize {
         if let Some(r) = self.min_root {
             let mut cnt = 0;
             let mut cur = r;
@@ -373,23 +1223,141 @@ impl<K: PartialOrd + Copy> FibHeap<K> {
     }
 }
 
+impl<K: PartialOrd + Clone + fmt::Debug> FibHeap<K> {
+    /// Graphviz DOT dump of the forest: one node per entry labelled
+    /// `(id, key)` (marked nodes are filled), solid parent→child edges and
+    /// dashed sibling-ring edges. Visits at most `n` nodes.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph FibHeap {\n");
+        let mut visited = vec![false; self.nodes.len()];
+        // (parent slot, first slot of the ring) still to be emitted
+        let mut rings: Vec<(Option<usize>, usize)> = Vec::new();
+        if let Some(r) = self.min_root {
+            rings.push((None, r));
+        }
+
+        let mut budget = self.n;
+        while let Some((parent, start)) = rings.pop() {
+            let ring = self.ring_slots(start);
+            for &i in &ring {
+                if visited[i] || budget == 0 {
+                    continue;
+                }
+                visited[i] = true;
+                budget -= 1;
+
+                let node = &self.nodes[i];
+                let (id, key) = &node.entry;
+                let style = if node.mark { ", style=filled" } else { "" };
+                out.push_str(&format!("  n{id} [label=\"({id}, {key:?})\"{style}];\n"));
+                if let Some(p) = parent {
+                    out.push_str(&format!("  n{} -> n{id};\n", self.nodes[p].entry.0));
+                }
+                if let Some(c) = node.child {
+                    rings.push((Some(i), c));
+                }
+            }
+            if ring.len() > 1 {
+                for &i in &ring {
+                    let (a, b) = (
+                        self.nodes[i].entry.0,
+                        self.nodes[self.nodes[i].right].entry.0,
+                    );
+                    out.push_str(&format!(
+                        "  n{a} -> n{b} [style=dashed, constraint=false];\n"
+                    ));
+                }
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<K> FibHeap<K> {
+    /// Number of key comparisons since creation or the last reset.
+    pub fn comparison_count(&self) -> u64 {
+        self.comparisons.get()
+    }
+
+    pub fn reset_comparison_count(&mut self) {
+        self.comparisons.reset();
+    }
+
+    /// Number of times the root list was consolidated.
+    pub fn consolidate_count(&self) -> u64 {
+        self.consolidations.get()
+    }
+
+    /// All operation counters at once, for asserting amortized bounds
+    /// empirically. Counts accumulate from creation.
+    pub fn op_counters(&self) -> OpCounters {
+        OpCounters {
+            comparisons: self.comparisons.get(),
+            links: self.links.get(),
+            cuts: self.cuts.get(),
+            cascading_cuts: self.cascading_cuts.get(),
+            consolidations: self.consolidations.get(),
+            sift_steps: 0,
+            scratch_grows: self.scratch_grows.get(),
+        }
+    }
+}
+
+impl<K: PartialOrd + Clone + Sub<Output = K>> FibHeap<K> {
+    /// `decrease_key` relative to the current key; `delta` must be positive.
+    /// Does nothing if `id` isn't in the heap.
+    pub fn decrease_key_by(&mut self, id: usize, delta: K) {
+        if !self.contains(id) {
+            return;
+        }
+        let old_key = &self.nodes[self.positions[id]].entry.1;
+        let new_key = old_key.clone() - delta;
+        debug_assert!(new_key < *old_key, "delta must be positive");
+        self.decrease_key(id, new_key);
+    }
+}
+
+#[cfg(feature = "bounded")]
+impl<K: PartialOrd + Clone + Bounded> FibHeap<K> {
+    /// The textbook `delete` for numeric keys: `decrease_key` to
+    /// `K::min_value()` (which cuts and cascades as usual and makes `id` the
+    /// min), then `delete_min`. Returns the key `id` had. A key already at
+    /// the bound, or tied with another one there, may not become the min;
+    /// `delete`'s direct cut finishes the job in that case.
+    pub fn delete_by_decrease(&mut self, id: usize) -> Option<(usize, K)> {
+        if !self.contains(id) {
+            return None;
+        }
+        let idx = self.positions[id];
+        let key = self.nodes[idx].entry.1.clone();
+        let bottom = K::min_value();
+        if bottom < key {
+            self.decrease_key(id, bottom);
+        }
+        self.delete(id).map(|(id, _)| (id, key))
+    }
+}
+
 #[cfg(debug_assertions)]
-impl<K: PartialOrd + Copy + std::fmt::Debug> FibHeap<K> {
+impl<K: PartialOrd + Clone + std::fmt::Debug> FibHeap<K> {
     /// O(total_nodes) scan that asserts both:
     ///   – the node removed by delete_min really had the global min key
     ///   – every parent key ≤ its children’s keys
     pub fn assert_heap_ok(&self, last_key: K) {
         for (i, node) in self.nodes.iter().enumerate() {
-            if self.positions[node.entry.0] == NOT_IN_HEAP {
+            if !self.is_live(i) {
                 continue; // node is already deleted
             }
-            let k = node.entry.1;
+            let k = &node.entry.1;
             assert!(
-                k >= last_key,
+                *k >= last_key,
                 "heap-order error: node #{i} key {k:?} < last pop {last_key:?}"
             );
             if let Some(p) = node.parent {
-                let pk = self.nodes[p].entry.1;
+                let pk = &self.nodes[p].entry.1;
                 assert!(
                     pk <= k,
                     "child key {k:?} < parent key {pk:?} (node #{i} → parent #{p})"
@@ -397,6 +1365,136 @@ impl<K: PartialOrd + Copy + std::fmt::Debug> FibHeap<K> {
             }
         }
     }
+
+    /// Structural check behind the amortized bounds: every node's child
+    /// ring holds exactly `degree` nodes and its subtree has at least
+    /// F(degree + 2) nodes. Pinpoints link/cut bugs `assert_heap_ok` misses.
+    pub fn assert_degree_invariant(&self) {
+        if let Some(m) = self.min_root {
+            for r in self.ring_slots(m) {
+                self.checked_subtree_size(r);
+            }
+        }
+    }
+
+    /// Parent/child links agree in both directions: every live node with a
+    /// `parent` is a member of that parent's child ring, and every member of
+    /// a child ring points back at the ring's owner. Root ring members have
+    /// no parent. Catches link/detach asymmetries that leave heap order
+    /// intact.
+    pub fn assert_no_orphans(&self) {
+        if let Some(m) = self.min_root {
+            for r in self.ring_slots(m) {
+                assert_eq!(self.nodes[r].parent, None, "root #{r} has a parent pointer");
+            }
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !self.is_live(i) {
+                continue;
+            }
+            if let Some(p) = node.parent {
+                let found = self.nodes[p]
+                    .child
+                    .is_some_and(|c| self.ring_slots(c).contains(&i));
+                assert!(found, "node #{i} is missing from the child ring of #{p}");
+            }
+            if let Some(c) = node.child {
+                for m in self.ring_slots(c) {
+                    assert_eq!(
+                        self.nodes[m].parent,
+                        Some(i),
+                        "node #{m} is in the child ring of #{i} but points elsewhere"
+                    );
+                }
+            }
+        }
+    }
+
+    /// `positions` is an exact inverse of the forest: every mapped id points
+    /// at an in-range slot holding that id, every node reachable from the
+    /// root ring is mapped back to its own slot, and every other id
+    /// (popped, deleted or never inserted) is `NOT_IN_HEAP`.
+    pub fn assert_positions_consistent(&self) {
+        let mut mapped = 0;
+        for (id, &idx) in self.positions.iter().enumerate() {
+            if idx == NOT_IN_HEAP {
+                continue;
+            }
+            mapped += 1;
+            assert!(
+                idx < self.nodes.len(),
+                "positions[{id}] = {idx} is past the end of the node arena"
+            );
+            assert_eq!(
+                self.nodes[idx].entry.0, id,
+                "positions[{id}] points at slot #{idx}, which holds another id"
+            );
+        }
+        let reachable = self.reachable_slots();
+        for &slot in &reachable {
+            let id = self.nodes[slot].entry.0;
+            assert_eq!(
+                self.positions.get(id),
+                Some(&slot),
+                "node #{slot} (id {id}) is in the forest but positions doesn't map it"
+            );
+        }
+        assert_eq!(
+            mapped,
+            reachable.len(),
+            "positions maps ids that are not in the forest"
+        );
+    }
+
+    /// Same forest shape as `other`: the same trees, each node with the same
+    /// id and key and the same children. Sibling (and root) order doesn't
+    /// matter; rings are compared sorted by id. Stronger than `==`, which
+    /// only compares contents, so it pins down what `consolidate` built.
+    pub fn structurally_eq(&self, other: &FibHeap<K>) -> bool {
+        self.n == other.n && Self::rings_eq(self, self.min_root, other, other.min_root)
+    }
+
+    fn rings_eq(a: &Self, ra: Option<usize>, b: &Self, rb: Option<usize>) -> bool {
+        let sorted = |h: &Self, r: Option<usize>| {
+            let mut slots = r.map_or_else(Vec::new, |r| h.ring_slots(r));
+            slots.sort_unstable_by_key(|&s| h.nodes[s].entry.0);
+            slots
+        };
+        let (sa, sb) = (sorted(a, ra), sorted(b, rb));
+        sa.len() == sb.len()
+            && sa.iter().zip(&sb).all(|(&x, &y)| {
+                a.nodes[x].entry == b.nodes[y].entry
+                    && Self::rings_eq(a, a.nodes[x].child, b, b.nodes[y].child)
+            })
+    }
+
+    /// Size of the subtree rooted at slot `i`, asserting the degree
+    /// invariant on the way down.
+    fn checked_subtree_size(&self, i: usize) -> usize {
+        let node = &self.nodes[i];
+        let children = node.child.map_or_else(Vec::new, |c| self.ring_slots(c));
+        assert_eq!(
+            children.len(),
+            node.degree,
+            "node #{i}: child ring length differs from stored degree"
+        );
+        let size = 1 + children
+            .iter()
+            .map(|&c| self.checked_subtree_size(c))
+            .sum::<usize>();
+
+        // F(degree + 2), with F(1) = F(2) = 1
+        let (mut a, mut b) = (1usize, 1usize);
+        for _ in 0..node.degree {
+            (a, b) = (b, a.saturating_add(b));
+        }
+        assert!(
+            size >= b,
+            "node #{i}: degree {} but subtree size only {size}",
+            node.degree
+        );
+        size
+    }
 }
 
 /* -------------------------------------------------------------------------- */
@@ -405,7 +1503,8 @@ impl<K: PartialOrd + Copy + std::fmt::Debug> FibHeap<K> {
 
 #[cfg(test)]
 mod tests {
-    use super::FibHeap;
+    use super::{ConsolidatePolicy, FibHeap, NOT_IN_HEAP};
+    use crate::HeapError;
 
     #[test]
     fn insert_and_get_min() {
@@ -415,6 +1514,16 @@ mod tests {
         assert_eq!(h.get_min(), Some(&(1, 10)));
     }
     #[test]
+    fn peek_matches_get_min() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        assert_eq!(h.peek(), None);
+        h.insert((0, 20));
+        h.insert((1, 10));
+        assert_eq!(h.peek(), h.get_min().copied());
+        h.delete_min();
+        assert_eq!(h.peek(), Some((0, 20)));
+    }
+    #[test]
     fn delete_min_order() {
         let mut h: FibHeap<i32> = FibHeap::new();
         h.insert((2, 30));
@@ -433,4 +1542,1133 @@ mod tests {
         h.decrease_key(8, 50);
         assert_eq!(h.get_min(), Some(&(8, 50)));
     }
+    #[test]
+    fn decrease_key_absent_id_is_noop() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        h.insert((100, 1));
+        h.decrease_key(50, 0);
+        h.decrease_key(200, 0);
+        h.delete_min();
+        h.decrease_key(100, 0);
+        assert!(h.is_empty());
+    }
+    #[test]
+    fn decrease_key_by_delta() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        h.insert((7, 100));
+        h.insert((8, 200));
+        h.insert((9, 300));
+        h.delete_min(); // builds a tree so 9 may sit below 8
+        h.decrease_key_by(9, 290);
+        assert_eq!(h.get_min(), Some(&(9, 10)));
+        assert_eq!(h.nodes[h.positions[9]].entry, (9, 10));
+
+        // absent ids, popped or never inserted, are ignored
+        h.decrease_key_by(9, 5);
+        h.delete_min();
+        h.decrease_key_by(9, 5);
+        h.decrease_key_by(42, 5);
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.get_min(), Some(&(8, 200)));
+    }
+    #[test]
+    fn clone_from_reuses_capacity() {
+        let template = FibHeap::build_heap(vec![(2, 50), (0, 10), (3, 20), (1, 5)]);
+        let mut work: FibHeap<i32> = FibHeap::new();
+
+        work.clone_from(&template);
+        while work.delete_min().is_some() {}
+        let node_cap = work.nodes.capacity();
+        let pos_cap = work.positions.capacity();
+
+        work.clone_from(&template);
+        assert!(work.nodes.capacity() >= node_cap);
+        assert!(work.positions.capacity() >= pos_cap);
+        assert_eq!(work.len(), 4);
+        assert_eq!(work.delete_min(), Some((1, 5)));
+        assert_eq!(work.delete_min(), Some((0, 10)));
+    }
+    #[test]
+    fn roots_and_children_after_consolidate() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..8 {
+            h.insert((i, i as i32 * 10));
+        }
+        h.delete_min();
+
+        // 7 nodes consolidate into binomial trees of size 4, 2 and 1
+        let mut roots = h.roots();
+        assert_eq!(roots[0], 1); // starts at the min
+        roots.sort();
+        assert_eq!(roots, vec![1, 5, 7]);
+
+        let mut c1 = h.children_of(1);
+        c1.sort();
+        assert_eq!(c1, vec![2, 3]);
+        assert_eq!(h.children_of(3), vec![4]);
+        assert_eq!(h.children_of(5), vec![6]);
+        for leaf in [2, 4, 6, 7] {
+            assert!(h.children_of(leaf).is_empty());
+        }
+        assert!(h.children_of(0).is_empty()); // deleted
+        assert!(h.children_of(99).is_empty()); // never inserted
+    }
+    #[test]
+    fn to_dot_declares_every_node() {
+        let empty: FibHeap<i32> = FibHeap::new();
+        assert_eq!(empty.to_dot(), "digraph FibHeap {\n}\n");
+
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..5 {
+            h.insert((i, i as i32 * 3));
+        }
+        h.delete_min();
+        let dot = h.to_dot();
+        assert_eq!(dot.matches("[label=").count(), 4);
+        assert!(dot.contains("n1 [label=\"(1, 3)\"]"));
+        // 4 nodes form one binomial tree: 3 parent→child edges
+        let tree_edges = dot
+            .lines()
+            .filter(|l| l.ends_with(";") && l.contains("->") && !l.contains("dashed"));
+        assert_eq!(tree_edges.count(), 3);
+    }
+    #[test]
+    fn root_counter_matches_walk() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        assert_eq!(h.root_count(), 0);
+        for i in 0..32 {
+            h.insert((i, (i as i32 * 37) % 64));
+            assert_eq!(h.root_count(), h.roots().len());
+        }
+        h.delete_min();
+        assert_eq!(h.root_count(), h.roots().len());
+        for i in (8..32).step_by(3) {
+            let key = h.get_min().unwrap().1 - 1 - i as i32;
+            h.decrease_key(i, key);
+            assert_eq!(h.root_count(), h.roots().len());
+        }
+        while h.delete_min().is_some() {
+            assert_eq!(h.root_count(), h.roots().len());
+        }
+        h.insert((0, 1));
+        h.clear();
+        assert_eq!(h.root_count(), 0);
+    }
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn comparison_count() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        // splice check + update_min per insert (the first only updates)
+        for i in 0..8 {
+            h.insert((i, i as i32));
+        }
+        assert_eq!(h.comparison_count(), 1 + 7 * 2);
+
+        h.reset_comparison_count();
+        h.delete_min();
+        // consolidating 7 roots needs at most one comparison per link plus
+        // a couple per rebuilt root
+        let count = h.comparison_count();
+        assert!((6..=6 + 2 * 3).contains(&count), "count = {count}");
+    }
+    #[test]
+    fn insert_observing_tracks_min() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..10 {
+            let key = 100 - i as i32;
+            assert_eq!(h.insert_observing((i, key)), Some((i, key)));
+        }
+        assert_eq!(h.insert_observing((10, 500)), Some((9, 91)));
+    }
+    #[test]
+    fn delete_min_single_root_fast_path() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        h.insert((0, 1_000));
+        for i in 0..100_000 {
+            h.insert((1, i % 10));
+            assert_eq!(h.delete_min(), Some((1, i % 10)));
+            assert_eq!(h.len(), 1);
+            assert_eq!(h.root_count(), 1);
+            assert_eq!(h.get_min(), Some(&(0, 1_000)));
+        }
+        #[cfg(feature = "metrics")]
+        assert_eq!(h.consolidate_count(), 0);
+
+        // a real multi-root pop still consolidates and stays correct
+        h.insert((2, 5));
+        h.insert((3, 7));
+        assert_eq!(h.delete_min(), Some((2, 5)));
+        #[cfg(feature = "metrics")]
+        assert_eq!(h.consolidate_count(), 1);
+        assert_eq!(h.delete_min(), Some((3, 7)));
+        assert_eq!(h.delete_min(), Some((0, 1_000)));
+        assert!(h.is_empty());
+    }
+    #[test]
+    fn to_vec_snapshot() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..6 {
+            h.insert((i, (i as i32 * 5) % 6));
+        }
+        h.delete_min();
+        h.insert((0, 9)); // recycle the popped id
+        h.decrease_key(3, -1);
+
+        let snapshot = h.to_vec();
+        assert_eq!(snapshot.len(), h.len());
+        assert_eq!(snapshot.capacity(), h.len());
+
+        let mut sorted = snapshot;
+        sorted.sort_by_key(|e| e.1);
+        assert_eq!(sorted, h.clone().into_sorted_vec());
+        assert_eq!(h.len(), 6); // the original is untouched
+    }
+    #[test]
+    fn lazy_policy_matches_eager() {
+        let mut eager: FibHeap<i64> = FibHeap::new();
+        let mut lazy: FibHeap<i64> = FibHeap::new();
+        lazy.set_consolidate_policy(ConsolidatePolicy::LazyThreshold(4.0));
+
+        let mut next_id = 0;
+        for round in 0..50i64 {
+            for j in 0..20i64 {
+                let key = (round * 7919 + j * 104_729) % 1_000;
+                eager.insert((next_id, key));
+                lazy.insert((next_id, key));
+                next_id += 1;
+            }
+            // decrease a still-live id to a fresh minimum
+            let target = next_id - 3;
+            let key = eager.get_min().unwrap().1 - 1;
+            eager.decrease_key(target, key);
+            lazy.decrease_key(target, key);
+
+            // ids of equal keys may come out in a different order
+            for _ in 0..15 {
+                assert_eq!(eager.get_min().map(|e| e.1), lazy.get_min().map(|e| e.1));
+                assert_eq!(
+                    eager.delete_min().map(|e| e.1),
+                    lazy.delete_min().map(|e| e.1)
+                );
+            }
+        }
+        assert!(lazy.root_count() >= eager.root_count());
+        #[cfg(feature = "metrics")]
+        assert!(lazy.consolidate_count() < eager.consolidate_count());
+        let keys =
+            |h: FibHeap<i64>| -> Vec<i64> { h.into_sorted_vec().iter().map(|e| e.1).collect() };
+        assert_eq!(keys(eager), keys(lazy));
+    }
+    #[test]
+    fn decrease_key_of_min_root_keeps_pointer() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..9 {
+            h.insert((i, 10 + i as i32));
+        }
+        h.delete_min(); // consolidate into trees
+        assert_eq!(h.get_min(), Some(&(1, 11)));
+
+        // the min root decreasing further stays the min
+        h.decrease_key(1, 5);
+        assert_eq!(h.get_min(), Some(&(1, 5)));
+        // later root splices must not move the pointer off it
+        h.insert((20, 7));
+        h.insert((21, 6));
+        assert_eq!(h.get_min(), Some(&(1, 5)));
+        h.decrease_key(1, 1);
+        assert_eq!(h.get_min(), Some(&(1, 1)));
+        assert_eq!(h.delete_min(), Some((1, 1)));
+        assert_eq!(h.get_min(), Some(&(21, 6)));
+    }
+
+    #[test]
+    fn decrease_key_cut_to_new_min() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..9 {
+            h.insert((i, 10 + i as i32));
+        }
+        h.delete_min();
+        // pick a node that has a parent so decreasing it cuts
+        let child = (2..9)
+            .find(|&id| h.nodes[h.positions[id]].parent.is_some())
+            .unwrap();
+        h.decrease_key(child, 0);
+        assert_eq!(h.get_min(), Some(&(child, 0)));
+        assert!(h.roots().contains(&child));
+
+        // a second cut below the new min must not steal the pointer
+        let other = (2..9)
+            .find(|&id| id != child && h.nodes[h.positions[id]].parent.is_some())
+            .unwrap();
+        h.decrease_key(other, 3);
+        assert_eq!(h.get_min(), Some(&(child, 0)));
+        assert_eq!(h.delete_min(), Some((child, 0)));
+        assert_eq!(h.delete_min(), Some((other, 3)));
+    }
+    #[test]
+    fn rebuild_after_corruption() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..16 {
+            h.insert((i, (i as i32 * 7) % 16));
+        }
+        h.delete_min();
+        h.assert_valid();
+
+        // forget one live node and mis-map two others
+        h.positions[3] = NOT_IN_HEAP;
+        let (a, b) = (h.positions[5], h.positions[6]);
+        h.positions[5] = b;
+        h.positions[6] = a;
+
+        h.rebuild();
+        h.assert_valid();
+        assert_eq!(h.len(), 12);
+        let mut ids: Vec<_> = h.to_vec().iter().map(|e| e.0).collect();
+        ids.sort();
+        let expected: Vec<_> = (1..16).filter(|id| ![3, 5, 6].contains(id)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "positions")]
+    fn assert_valid_detects_corruption() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        h.insert((0, 1));
+        h.insert((1, 2));
+        h.positions.swap(0, 1);
+        h.assert_valid();
+    }
+    #[test]
+    fn stable_mode_pops_equal_keys_by_id() {
+        // a fixed scramble of 0..64
+        let ids: Vec<usize> = (0..64).map(|i| (i * 37 + 11) % 64).collect();
+
+        let mut h: FibHeap<u32> = FibHeap::new();
+        h.set_stable(true);
+        for &id in &ids {
+            h.insert((id, 7));
+        }
+        // interleave a few pops and decreases to exercise link and cut
+        assert_eq!(h.delete_min(), Some((0, 7)));
+        h.insert((100, 9));
+        h.decrease_key(100, 7);
+        assert_eq!(h.delete_min(), Some((1, 7)));
+
+        let popped: Vec<usize> = h.into_sorted_vec().iter().map(|e| e.0).collect();
+        let mut expected: Vec<usize> = (2..64).collect();
+        expected.push(100);
+        assert_eq!(popped, expected);
+    }
+    #[test]
+    #[cfg(debug_assertions)]
+    fn degree_invariant_under_random_ops() {
+        let mut state = 0x2545_f491_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut h: FibHeap<u64> = FibHeap::new();
+        let mut keys = vec![None; 2000];
+        for step in 0..6000 {
+            match next() % 4 {
+                0 | 1 => {
+                    let id = (next() % 2000) as usize;
+                    if keys[id].is_none() {
+                        let k = next() % 100_000;
+                        h.insert((id, k));
+                        keys[id] = Some(k);
+                    }
+                }
+                2 => {
+                    let id = (next() % 2000) as usize;
+                    if let Some(k) = keys[id] {
+                        if k > 0 {
+                            let new_k = next() % k;
+                            h.decrease_key(id, new_k);
+                            keys[id] = Some(new_k);
+                        }
+                    }
+                }
+                _ => {
+                    if let Some((id, _)) = h.delete_min() {
+                        keys[id] = None;
+                    }
+                }
+            }
+            if step % 50 == 0 {
+                h.assert_degree_invariant();
+            }
+        }
+        h.assert_degree_invariant();
+        h.assert_valid();
+    }
+    #[test]
+    fn eq_ignores_forest_shape() {
+        let mut a: FibHeap<i32> = FibHeap::new();
+        let mut b: FibHeap<i32> = FibHeap::new();
+        for i in 0..10 {
+            a.insert((i, i as i32 % 4));
+            b.insert((9 - i, (9 - i) as i32 % 4));
+        }
+        a.insert((10, -1));
+        a.delete_min(); // consolidates a, b stays a flat root list
+        b.insert((10, -1));
+        assert!(a != b);
+        b.delete_min();
+        assert!(a == b);
+
+        a.decrease_key(7, -5);
+        assert!(a != b);
+    }
+    #[test]
+    fn shrink_positions_after_high_id() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        h.insert((3, 5));
+        h.insert((1, 9));
+        h.insert((100_000, 1));
+        assert!(h.positions_capacity() > 100_000);
+
+        h.shrink_positions(); // id 100_000 is still live
+        assert!(h.positions_capacity() > 100_000);
+
+        assert_eq!(h.delete_min(), Some((100_000, 1)));
+        h.shrink_positions();
+        assert!(h.positions_capacity() < 100);
+        h.assert_valid();
+        assert_eq!(h.to_vec().len(), 2); // dead slot with the high id is skipped
+
+        h.decrease_key(1, 2);
+        assert_eq!(h.delete_min(), Some((1, 2)));
+        h.insert((10, 0));
+        assert_eq!(h.into_sorted_vec(), vec![(10, 0), (3, 5)]);
+    }
+    #[test]
+    fn tuple_keys_are_lexicographic() {
+        let mut h: FibHeap<(u32, u32)> = FibHeap::new();
+        for (id, key) in [
+            (0, (3, 1)),
+            (1, (2, 8)),
+            (2, (3, 0)),
+            (3, (2, 5)),
+            (4, (9, 9)),
+        ] {
+            h.insert((id, key));
+        }
+        assert_eq!(h.delete_min(), Some((3, (2, 5))));
+        h.decrease_key(1, (2, 0));
+        h.decrease_key(4, (3, 0)); // ties with id 2 on both components
+        h.assert_valid();
+
+        let keys: Vec<_> = h.into_sorted_vec().into_iter().map(|e| e.1).collect();
+        assert_eq!(keys, vec![(2, 0), (3, 0), (3, 0), (3, 1)]);
+    }
+    #[test]
+    fn marked_count_tracks_cuts() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        for i in 0..17 {
+            h.insert((i, i as i32));
+        }
+        h.delete_min(); // leaves a single binomial tree of 16 nodes
+        assert_eq!(h.get_min_id(), Some(1));
+        assert_eq!(h.marked_count(), 0);
+
+        // a non-root node with two children
+        let root = h.min_root.unwrap();
+        let c = h
+            .children_of(h.nodes[root].entry.0)
+            .into_iter()
+            .find(|&id| h.children_of(id).len() >= 2)
+            .unwrap();
+        let grandkids = h.children_of(c);
+
+        h.decrease_key(grandkids[0], -10);
+        assert_eq!(h.marked_count(), 1); // c lost its first child
+        assert_eq!(h.get_min_id(), Some(grandkids[0]));
+
+        h.decrease_key(grandkids[1], -20);
+        assert_eq!(h.marked_count(), 0); // c was cut in turn, its parent is a root
+        assert_eq!(h.get_min_id(), Some(grandkids[1]));
+        h.assert_valid();
+    }
+    #[test]
+    fn try_decrease_key_rejects_bad_input() {
+        let mut h: FibHeap<f64> = FibHeap::new();
+        for i in 0..8 {
+            h.insert((i, i as f64));
+        }
+        h.delete_min();
+        let before = h.to_vec();
+
+        assert_eq!(
+            h.try_decrease_key(5, f64::NAN),
+            Err(HeapError::Incomparable)
+        );
+        assert_eq!(h.try_decrease_key(5, 6.0), Err(HeapError::KeyNotSmaller(5)));
+        assert_eq!(h.try_decrease_key(0, -1.0), Err(HeapError::NotPresent(0)));
+        assert_eq!(h.try_decrease_key(99, -1.0), Err(HeapError::NotPresent(99)));
+        assert_eq!(h.to_vec(), before);
+
+        assert_eq!(h.try_decrease_key(5, -1.0), Ok(()));
+        assert_eq!(h.get_min(), Some(&(5, -1.0)));
+    }
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn op_counters_respect_amortized_bounds() {
+        let n = 4096;
+        let mut h: FibHeap<u64> = FibHeap::new();
+        for i in 0..n {
+            h.insert((i, (i as u64 * 2_654_435_761) % 100_003));
+        }
+        for i in (0..n).step_by(3) {
+            h.delete_min();
+            if h.positions[i] != NOT_IN_HEAP {
+                h.decrease_key(i, 0);
+            }
+        }
+        while h.delete_min().is_some() {}
+
+        let ops = h.op_counters();
+        // every link merges two trees into one and every pop removes one,
+        // so links = (children promoted by pops) + cuts. a node's degree
+        // is at most log_phi(n), which caps the promotions at n * log_phi(n)
+        let max_degree = ((n as f64).ln() / 1.618_f64.ln()) as u64;
+        assert!(ops.links <= n as u64 * max_degree + ops.cuts, "{ops:?}");
+        // and far below that in practice: amortized ~log2(n) per pop
+        assert!(ops.links <= n as u64 * 13, "{ops:?}");
+        assert!(ops.cascading_cuts <= ops.cuts);
+        assert!(ops.cuts <= (n / 3 + 1) as u64 * 2, "{ops:?}");
+        assert_eq!(ops.sift_steps, 0);
+    }
+    #[test]
+    fn union_disjoint_and_remapped() {
+        let build = |ids: std::ops::Range<usize>, f: fn(usize) -> i64| {
+            let mut h: FibHeap<i64> = FibHeap::new();
+            for id in ids {
+                h.insert((id, f(id)));
+            }
+            h.delete_min(); // give both a real forest, not just a root list
+            h
+        };
+
+        let mut a = build(0..100, |id| (id * 37 % 100) as i64);
+        let b = build(0..100, |id| (id * 53 % 100) as i64 + 1000);
+        assert_eq!(a.union(b.clone()), Err(HeapError::DuplicateId(1)));
+        assert_eq!(a.len(), 99);
+
+        let mapping = a.union_remap(b.clone());
+        assert_eq!(a.len(), 198);
+        a.assert_valid();
+
+        // 99 live ids of b, consistently shifted past a's largest id (99)
+        assert_eq!(mapping.len(), 99);
+        for &(old, new) in &mapping {
+            assert_eq!(new, old + 100);
+            assert_eq!(
+                a.nodes[a.positions[new]].entry.1,
+                (old * 53 % 100) as i64 + 1000
+            );
+        }
+
+        let keys: Vec<_> = a.into_sorted_vec().iter().map(|e| e.1).collect();
+        let mut expected: Vec<i64> = (1..100).chain(1001..1100).collect();
+        expected.sort();
+        assert_eq!(keys, expected);
+
+        // disjoint ids union without remapping
+        let mut c = build(0..10, |id| id as i64);
+        let d = build(10..20, |id| -(id as i64));
+        c.union(d).unwrap();
+        c.assert_valid();
+        assert_eq!(c.get_min(), Some(&(18, -18)));
+        assert_eq!(c.len(), 18);
+    }
+    #[test]
+    fn from_iter_and_extend() {
+        let first: Vec<(usize, i32)> = (0..50).map(|id| (id, (id * 17 % 50) as i32)).collect();
+        let more: Vec<(usize, i32)> = (50..80).map(|id| (id, (id * 7 % 40) as i32 - 20)).collect();
+
+        let mut h: FibHeap<i32> = first.iter().copied().collect();
+        assert_eq!(h.len(), 50);
+        assert_eq!(h.root_count(), 50); // no consolidation yet
+        assert_eq!(h.get_min(), Some(&(0, 0)));
+
+        h.extend(more.iter().copied());
+        assert_eq!(h.len(), 80);
+        assert_eq!(h.get_min().map(|e| e.1), more.iter().map(|e| e.1).min());
+        h.assert_valid();
+
+        let mut reference: Vec<i32> = first.iter().chain(&more).map(|e| e.1).collect();
+        reference.sort();
+        let keys: Vec<i32> = h.into_sorted_vec().iter().map(|e| e.1).collect();
+        assert_eq!(keys, reference);
+    }
+    #[test]
+    fn force_consolidate_shrinks_root_list() {
+        let mut h: FibHeap<u32> = FibHeap::new();
+        h.force_consolidate(); // empty
+        h.insert((0, 5));
+        h.force_consolidate(); // single root
+        assert_eq!(h.root_count(), 1);
+
+        for id in 1..1000 {
+            h.insert((id, (id as u32 * 7919) % 1000));
+        }
+        assert_eq!(h.root_count(), 1000);
+        h.force_consolidate();
+        // equal-degree linking leaves one binomial tree per set bit of n
+        assert_eq!(h.root_count(), 1000u32.count_ones() as usize);
+        assert!(h.root_count() <= 10);
+        assert_eq!(h.get_min().map(|e| e.1), Some(1));
+        h.assert_valid();
+        assert_eq!(h.delete_min().map(|e| e.1), Some(1));
+    }
+
+    #[test]
+    fn cut_observer_sees_cascade() {
+        use std::sync::{Arc, Mutex};
+
+        // 9 nodes, pop one: the other 8 consolidate into a single B3
+        let mut h: FibHeap<i32> = (0..9).map(|i| (i, i as i32 * 10)).collect();
+        h.delete_min();
+        assert_eq!(h.root_count(), 1);
+
+        // the degree-2 child of the root: a non-root node with two children
+        let root = h.min_root.unwrap();
+        let root_id = h.nodes[root].entry.0;
+        let mut p = h.nodes[root].child.unwrap();
+        while h.nodes[p].degree != 2 {
+            p = h.nodes[p].right;
+        }
+        let p_id = h.nodes[p].entry.0;
+        let c1 = h.nodes[p].child.unwrap();
+        let c2 = h.nodes[c1].right;
+        let (c1_id, c2_id) = (h.nodes[c1].entry.0, h.nodes[c2].entry.0);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        h.set_cut_observer(move |node, parent, marked| {
+            sink.lock().unwrap().push((node, parent, marked));
+        });
+
+        // first cut marks p, the second cascades p off the root
+        h.decrease_key(c1_id, -1);
+        h.decrease_key(c2_id, -2);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (c1_id, p_id, false),
+                (c2_id, p_id, false),
+                (p_id, root_id, true),
+            ]
+        );
+        h.assert_valid();
+
+        h.clear_cut_observer();
+        h.decrease_key(p_id, -3);
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn delete_interior_node() {
+        let mut h: FibHeap<i32> = (0..16).map(|i| (i, i as i32)).collect();
+        h.delete_min();
+        // both sit below the root of the consolidated forest
+        assert_eq!(h.delete(9), Some((9, 9)));
+        assert_eq!(h.delete(9), None);
+        assert_eq!(h.delete(1), Some((1, 1)));
+        assert!(!h.contains(1) && h.contains(2));
+        h.assert_valid();
+
+        let rest: Vec<_> = std::iter::from_fn(|| h.delete_min().map(|e| e.0)).collect();
+        let expected: Vec<_> = (2..16).filter(|&i| i != 9).collect();
+        assert_eq!(rest, expected);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn no_orphans_under_random_ops() {
+        let mut state = 0x9e37_79b9_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut h: FibHeap<u64> = FibHeap::new();
+        let mut keys = vec![None; 500];
+        for _ in 0..3000 {
+            let id = (next() % 500) as usize;
+            match next() % 5 {
+                0 | 1 => {
+                    if keys[id].is_none() {
+                        let k = next() % 100_000;
+                        h.insert((id, k));
+                        keys[id] = Some(k);
+                    }
+                }
+                2 => {
+                    if let Some(k) = keys[id].filter(|&k| k > 0) {
+                        let new_k = next() % k;
+                        h.decrease_key(id, new_k);
+                        keys[id] = Some(new_k);
+                    }
+                }
+                3 => {
+                    if h.delete(id).is_some() {
+                        keys[id] = None;
+                    }
+                }
+                _ => {
+                    if let Some((id, _)) = h.delete_min() {
+                        keys[id] = None;
+                    }
+                }
+            }
+            h.assert_no_orphans();
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "child ring")]
+    fn no_orphans_catches_dangling_parent() {
+        let mut h: FibHeap<i32> = (0..8).map(|i| (i, i as i32)).collect();
+        h.delete_min();
+        // point a child at a node whose child ring doesn't contain it
+        let root = h.min_root.unwrap();
+        let child = h.nodes[root].child.unwrap();
+        let sibling = h.nodes[child].right;
+        h.nodes[child].parent = Some(sibling);
+        h.assert_no_orphans();
+    }
+
+    #[test]
+    fn with_min_and_pop_and() {
+        let mut h: FibHeap<String> = FibHeap::new();
+        assert_eq!(h.with_min(|_, k| k.len()), None);
+        h.insert((0, "pear".repeat(100)));
+        h.insert((1, "apple".repeat(100)));
+
+        assert_eq!(h.with_min(|id, k| (id, k.len())), Some((1, 500)));
+        assert_eq!(
+            h.pop_and(|id, k| (id, k.starts_with("apple"))),
+            Some((1, true))
+        );
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.get_min_id(), Some(0));
+    }
+
+    #[test]
+    fn iter_by_reference() {
+        use std::collections::BTreeSet;
+
+        let empty: FibHeap<u32> = FibHeap::new();
+        assert_eq!((&empty).into_iter().count(), 0);
+
+        let items: Vec<(usize, u32)> = (0..40).map(|i| (i, (i as u32 * 7) % 23)).collect();
+        let mut h: FibHeap<u32> = items.iter().cloned().collect();
+        // give the forest some depth, then cut a few nodes out of it
+        h.delete_min();
+        h.decrease_key(30, 0);
+        h.decrease_key(17, 0);
+
+        let mut expected: BTreeSet<(usize, u32)> = items.into_iter().skip(1).collect();
+        for id in [30, 17] {
+            let old = *expected.iter().find(|e| e.0 == id).unwrap();
+            expected.remove(&old);
+            expected.insert((id, 0));
+        }
+
+        assert_eq!(h.iter().len(), 39);
+        let mut seen = BTreeSet::new();
+        for e in &h {
+            assert!(seen.insert(*e), "{e:?} visited twice");
+        }
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn gc_keeps_node_arena_bounded() {
+        let mut h: FibHeap<u64> = FibHeap::new();
+        let mut next_id = 0;
+        let mut churn = |h: &mut FibHeap<u64>, rounds: usize| {
+            for _ in 0..rounds {
+                for _ in 0..8 {
+                    h.insert((next_id % 64, (next_id as u64 * 2654435761) % 1000));
+                    next_id += 1;
+                }
+                for _ in 0..8 {
+                    h.delete_min();
+                }
+            }
+        };
+
+        for id in 1000..1032 {
+            h.insert((id, id as u64));
+        }
+        churn(&mut h, 200);
+        let cap = h.nodes.capacity();
+        churn(&mut h, 5000);
+        assert_eq!(h.nodes.capacity(), cap);
+        assert!(h.nodes.len() <= 2 * h.len() + 1);
+        h.assert_valid();
+
+        // turned off, the arena keeps every slot
+        h.set_gc_threshold(1.0);
+        let before = h.nodes.len();
+        churn(&mut h, 10);
+        assert_eq!(h.nodes.len(), before + 80);
+        let rest: Vec<_> = std::iter::from_fn(|| h.delete_min().map(|e| e.0)).collect();
+        assert_eq!(rest, (1000..1032).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "id too large")]
+    fn rejects_max_id() {
+        let mut h = FibHeap::new();
+        h.insert((1 << 20, 1));
+        assert_eq!(h.get_min_id(), Some(1 << 20));
+        h.insert((usize::MAX, 0));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn positions_consistent_under_random_ops() {
+        let mut state = 0x5851_f42d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut h: FibHeap<u64> = FibHeap::new();
+        let mut keys = vec![None; 300];
+        for _ in 0..3000 {
+            let id = (next() % 300) as usize;
+            match next() % 4 {
+                0 => {
+                    if keys[id].is_none() {
+                        let k = next() % 10_000;
+                        h.insert((id, k));
+                        keys[id] = Some(k);
+                    }
+                }
+                1 => {
+                    if let Some(k) = keys[id].filter(|&k| k > 0) {
+                        let new_k = next() % k;
+                        h.decrease_key(id, new_k);
+                        keys[id] = Some(new_k);
+                    }
+                }
+                2 => {
+                    if h.delete(id).is_some() {
+                        keys[id] = None;
+                    }
+                }
+                _ => {
+                    if let Some((id, _)) = h.delete_min() {
+                        keys[id] = None;
+                    }
+                }
+            }
+            h.assert_positions_consistent();
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "positions")]
+    fn positions_check_catches_stale_entry() {
+        let mut h: FibHeap<i32> = (0..6).map(|i| (i, i as i32)).collect();
+        h.set_gc_threshold(1.0);
+        h.delete_min();
+        // id 0 was popped, but pretend it still lives in its old slot
+        h.positions[0] = 0;
+        h.assert_positions_consistent();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn structural_equality() {
+        let build = |policy| {
+            let mut h: FibHeap<u32> = FibHeap::new();
+            h.set_consolidate_policy(policy);
+            for id in 0..20 {
+                h.insert((id, (id as u32 * 13) % 29));
+            }
+            h.delete_min();
+            h.decrease_key(11, 0);
+            h
+        };
+
+        let a = build(ConsolidatePolicy::Eager);
+        let b = build(ConsolidatePolicy::Eager);
+        assert!(a.structurally_eq(&b) && b.structurally_eq(&a));
+        assert!(a.structurally_eq(&a.clone()));
+
+        // same contents, but the lazy heap never consolidated
+        let lazy = build(ConsolidatePolicy::LazyThreshold(1e9));
+        assert!(a == lazy);
+        assert!(!a.structurally_eq(&lazy));
+
+        let mut c = build(ConsolidatePolicy::Eager);
+        c.decrease_key(19, 1);
+        assert!(!a.structurally_eq(&c));
+    }
+
+    #[test]
+    fn insert_batch_returns_min() {
+        let mut h: FibHeap<i32> = FibHeap::new();
+        assert_eq!(h.insert_batch(&[]), None);
+        h.insert((0, 50));
+        let batch: Vec<_> = (1..64).map(|id| (id, 100 - id as i32)).collect();
+        assert_eq!(h.insert_batch(&batch), Some((63, 37)));
+        assert_eq!(h.insert_batch(&[(500, 60)]), Some((63, 37)));
+        assert_eq!(h.len(), 65);
+        h.assert_valid();
+    }
+
+    #[test]
+    fn cascade_through_premarked_chain() {
+        // 16 nodes after the pop consolidate into one binomial tree B4
+        let mut h: FibHeap<i32> = (0..17).map(|i| (i, i as i32 * 10)).collect();
+        h.delete_min();
+        assert_eq!(h.root_count(), 1);
+
+        // follow the largest-degree child down to a leaf at depth 4
+        let root = h.min_root.unwrap();
+        let mut chain = Vec::new();
+        let mut cur = root;
+        while let Some(c) = h.nodes[cur].child {
+            let mut best = c;
+            for s in h.ring_slots(c) {
+                if h.nodes[s].degree > h.nodes[best].degree {
+                    best = s;
+                }
+            }
+            chain.push(h.nodes[best].entry.0);
+            cur = best;
+        }
+        assert_eq!(chain.len(), 4);
+        let leaf = chain.pop().unwrap();
+
+        // mark every ancestor below the root
+        for &id in &chain {
+            h.set_mark_for_test(id, true);
+        }
+        assert!(chain.iter().all(|&id| h.is_marked(id)));
+
+        h.decrease_key(leaf, -1);
+        assert_eq!(h.root_count(), 5);
+        for &id in chain.iter().chain([&leaf]) {
+            assert_eq!(h.nodes[h.positions[id]].parent, None);
+            assert!(!h.is_marked(id));
+        }
+        assert_eq!(h.get_min(), Some(&(leaf, -1)));
+        h.assert_valid();
+    }
+
+    #[test]
+    fn bulk_promotion_matches_per_child() {
+        // 129 items, one pop: the remaining 128 consolidate into a single
+        // degree-7 tree whose root is the min
+        let mut a: FibHeap<i64> = FibHeap::new();
+        for id in 0..129 {
+            a.insert((id, (id as i64 * 37) % 129));
+        }
+        a.delete_min();
+        assert_eq!(a.root_count(), 1);
+        let root = a.min_root.unwrap();
+        assert_eq!(a.nodes[root].degree, 7);
+        a.set_mark_for_test(a.nodes[a.nodes[root].child.unwrap()].entry.0, true);
+
+        let mut b = a.clone();
+        assert_eq!(a.delete_min(), b.delete_min_per_child());
+        assert_eq!(a.root_count(), b.root_count());
+        #[cfg(debug_assertions)]
+        assert!(a.structurally_eq(&b));
+        a.assert_valid();
+
+        // delete() promotes under a forced min whose children can beat it;
+        // afterwards both ways keep popping the same sequence
+        let id = a.nodes[a.nodes[a.min_root.unwrap()].child.unwrap()].entry.0;
+        let mut b = a.clone();
+        assert_eq!(a.delete(id), b.delete(id));
+        a.assert_valid();
+        loop {
+            let x = a.delete_min();
+            assert_eq!(x, b.delete_min_per_child());
+            if x.is_none() {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn push_assigns_ids() {
+        let mut h = FibHeap::new();
+        let ids: Vec<usize> = [40, 10, 30, 20].into_iter().map(|k| h.push(k)).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+
+        h.decrease_key(ids[2], 5);
+        assert_eq!(h.delete_min(), Some((2, 5)));
+        assert_eq!(h.push(50), 2);
+        h.insert((4, 1));
+        assert_eq!(h.push(60), 5);
+        h.delete(0);
+        assert_eq!(h.push(70), 0);
+        assert_eq!(h.len(), 6);
+        h.assert_valid();
+
+        h.clear();
+        assert_eq!(h.push(1), 0);
+    }
+
+    #[cfg(feature = "bounded")]
+    #[test]
+    fn delete_by_decrease_interior() {
+        let mut h: FibHeap<i64> = FibHeap::new();
+        for id in 0..64 {
+            h.insert((id, (id as i64 * 37) % 64 - 20));
+        }
+        h.delete_min();
+        h.insert((0, i64::MIN)); // a key already at the bound
+
+        let mut oracle = h.to_vec();
+        // interior nodes: ids with a parent
+        let interior: Vec<usize> = (1..64)
+            .filter(|&id| h.nodes[h.positions[id]].parent.is_some())
+            .take(5)
+            .collect();
+        assert_eq!(interior.len(), 5);
+        for &id in &interior {
+            let key = oracle.iter().find(|e| e.0 == id).unwrap().1;
+            assert_eq!(h.delete_by_decrease(id), Some((id, key)));
+            oracle.retain(|e| e.0 != id);
+            assert!(!h.contains(id));
+            h.assert_valid();
+        }
+        assert_eq!(h.delete_by_decrease(0), Some((0, i64::MIN)));
+        assert_eq!(h.delete_by_decrease(0), None);
+        oracle.retain(|e| e.0 != 0);
+
+        oracle.sort_by_key(|e| e.1);
+        let popped: Vec<i64> = std::iter::from_fn(|| h.delete_min().map(|e| e.1)).collect();
+        assert_eq!(popped, oracle.iter().map(|e| e.1).collect::<Vec<_>>());
+
+        let mut f = FibHeap::new();
+        f.insert((0, 1.5));
+        f.insert((1, 2.5));
+        assert_eq!(f.delete_by_decrease(1), Some((1, 2.5)));
+        assert_eq!(f.peek(), Some((0, 1.5)));
+    }
+
+    #[test]
+    fn consolidate_traced_reports_links() {
+        // four singleton roots of equal degree, walked from the min: 0 goes
+        // under 1, 2 under 3, then the two degree-1 trees link
+        let build = || {
+            let mut h = FibHeap::new();
+            for (id, key) in [(0, 40), (1, 10), (2, 30), (3, 20)] {
+                h.insert((id, key));
+            }
+            h
+        };
+        let mut traced = build();
+        let start = traced.min_root.unwrap();
+        let order: Vec<usize> = traced
+            .ring_slots(start)
+            .into_iter()
+            .map(|s| traced.nodes[s].entry.0)
+            .collect();
+        assert_eq!(order, vec![1, 0, 2, 3]);
+
+        let mut links = Vec::new();
+        traced.consolidate_traced(|c, p| links.push((c, p)));
+        assert_eq!(links, vec![(0, 1), (2, 3), (3, 1)]);
+        assert_eq!(traced.root_count(), 1);
+        assert_eq!(traced.get_min(), Some(&(1, 10)));
+        traced.assert_valid();
+
+        let mut plain = build();
+        plain.force_consolidate();
+        #[cfg(debug_assertions)]
+        assert!(traced.structurally_eq(&plain));
+        assert_eq!(plain.delete_min(), traced.delete_min());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn reserved_scratch_avoids_first_pop_growth() {
+        let n = 1000;
+        let fill = |h: &mut FibHeap<u64>| {
+            for i in 0..n {
+                h.insert((i, (i as u64 * 7919) % 1009));
+            }
+        };
+
+        let mut cold = FibHeap::new();
+        fill(&mut cold);
+        cold.delete_min();
+        assert!(cold.op_counters().scratch_grows > 0);
+
+        let max_degree = (n as f64).log2().ceil() as usize + 2;
+        let mut warm = FibHeap::with_scratch_capacity(max_degree, n);
+        fill(&mut warm);
+        assert_eq!(warm.root_count(), n);
+        assert_eq!(warm.delete_min(), cold_min(n));
+        assert_eq!(warm.op_counters().scratch_grows, 0);
+        warm.assert_valid();
+    }
+
+    #[cfg(feature = "metrics")]
+    fn cold_min(n: usize) -> Option<(usize, u64)> {
+        (0..n)
+            .map(|i| (i, (i as u64 * 7919) % 1009))
+            .min_by_key(|e| e.1)
+    }
+
+    #[test]
+    fn try_delete_min_rejects_nan() {
+        // NaN != NaN, so compare bit patterns
+        let snapshot = |h: &FibHeap<f64>| {
+            let mut v: Vec<_> = h.iter().map(|e| (e.0, e.1.to_bits())).collect();
+            v.sort();
+            (v, h.min_root, h.root_count())
+        };
+
+        // a NaN on the root list
+        let mut h: FibHeap<f64> = (0..8).map(|i| (i, i as f64)).collect();
+        h.insert((8, f64::NAN));
+        let before = snapshot(&h);
+        assert_eq!(h.try_delete_min(), Err(HeapError::Incomparable));
+        assert_eq!(snapshot(&h), before);
+
+        // a NaN among the min's children, met only once they are promoted
+        let mut h: FibHeap<f64> = (0..9).map(|i| (i, i as f64)).collect();
+        h.delete_min();
+        assert_eq!(h.root_count(), 1);
+        let child = h.nodes[h.min_root.unwrap()].child.unwrap();
+        h.nodes[child].entry.1 = f64::NAN;
+        let before = snapshot(&h);
+        assert_eq!(h.try_delete_min(), Err(HeapError::Incomparable));
+        assert_eq!(snapshot(&h), before);
+        assert_eq!(h.nodes[h.min_root.unwrap()].child, Some(child));
+        assert_eq!(h.nodes[child].parent, h.min_root);
+
+        h.nodes[child].entry.1 = 100.0;
+        assert_eq!(h.try_delete_min(), Ok(Some((1, 1.0))));
+        h.assert_valid();
+        let rest: Vec<f64> = std::iter::from_fn(|| h.try_delete_min().unwrap())
+            .map(|e| e.1)
+            .collect();
+        assert_eq!(rest.len(), 7);
+        assert!(rest.windows(2).all(|w| w[0] <= w[1]) && rest[6] == 100.0);
+        assert_eq!(FibHeap::<f64>::new().try_delete_min(), Ok(None));
+    }
 }
@@ -0,0 +1,114 @@
+use crate::{HeapError, MinHeap};
+
+// an id together with the generation it was inserted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    pub id: usize,
+    pub gen: u64,
+}
+
+// MinHeap wrapper that hands out generational handles, so a handle kept
+// around after its id was popped and reinserted can't touch the new entry
+pub struct TrackedMinHeap<K> {
+    heap: MinHeap<K>,
+    // generation of the latest insert of each id
+    gens: Vec<u64>,
+}
+
+impl<K: PartialOrd + Clone> Default for TrackedMinHeap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone> TrackedMinHeap<K> {
+    pub fn new() -> Self {
+        TrackedMinHeap {
+            heap: MinHeap::new(),
+            gens: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn insert(&mut self, item: (usize, K)) -> Handle {
+        let id = item.0;
+        if id >= self.gens.len() {
+            self.gens.resize(id + 1, 0);
+        }
+        self.gens[id] += 1;
+        self.heap.insert(item);
+        Handle {
+            id,
+            gen: self.gens[id],
+        }
+    }
+
+    pub fn get_min(&self) -> Option<&(usize, K)> {
+        self.heap.get_min()
+    }
+
+    pub fn delete_min(&mut self) -> Option<(usize, K)> {
+        self.heap.delete_min()
+    }
+
+    // true if the entry the handle was issued for is still in the heap
+    pub fn contains(&self, h: &Handle) -> bool {
+        self.gens.get(h.id) == Some(&h.gen) && self.heap.contains(h.id)
+    }
+
+    pub fn decrease_key(&mut self, h: &Handle, new_key: K) -> Result<(), HeapError> {
+        self.check(h)?;
+        self.heap.decrease_key(h.id, new_key);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, h: &Handle) -> Result<(usize, K), HeapError> {
+        self.check(h)?;
+        self.heap.delete(h.id).ok_or(HeapError::StaleHandle)
+    }
+
+    fn check(&self, h: &Handle) -> Result<(), HeapError> {
+        if self.contains(h) {
+            Ok(())
+        } else {
+            Err(HeapError::StaleHandle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycled_id_rejects_old_handle() {
+        let mut th: TrackedMinHeap<i32> = TrackedMinHeap::new();
+        let old = th.insert((0, 10));
+        th.insert((1, 20));
+        assert!(th.contains(&old));
+        assert_eq!(th.delete_min(), Some((0, 10)));
+
+        // popped but not yet reused
+        assert!(!th.contains(&old));
+        assert_eq!(th.decrease_key(&old, 1), Err(HeapError::StaleHandle));
+
+        // id 0 is recycled for a different element
+        let new = th.insert((0, 30));
+        assert_ne!(old, new);
+        assert_eq!(th.decrease_key(&old, 1), Err(HeapError::StaleHandle));
+        assert_eq!(th.delete(&old), Err(HeapError::StaleHandle));
+        assert_eq!(th.get_min(), Some(&(1, 20)));
+
+        th.decrease_key(&new, 5).unwrap();
+        assert_eq!(th.get_min(), Some(&(0, 5)));
+        assert_eq!(th.delete(&new), Ok((0, 5)));
+        assert_eq!(th.len(), 1);
+    }
+}
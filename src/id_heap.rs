@@ -0,0 +1,85 @@
+use crate::MinHeap;
+
+// a heap of bare ids that pops them in ascending order, for when the id is
+// the priority. thin wrapper around MinHeap<usize> storing (id, id)
+#[derive(Clone, Default)]
+pub struct IdHeap {
+    heap: MinHeap<usize>,
+}
+
+impl IdHeap {
+    pub fn new() -> Self {
+        IdHeap {
+            heap: MinHeap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    // ids must be unique, same as for MinHeap
+    pub fn insert(&mut self, id: usize) {
+        self.heap.insert((id, id));
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.heap.contains(id)
+    }
+
+    pub fn peek(&self) -> Option<usize> {
+        self.heap.get_min().map(|&(id, _)| id)
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        self.heap.delete_min().map(|(id, _)| id)
+    }
+
+    // remove id wherever it is, false if it wasn't in the heap
+    pub fn remove(&mut self, id: usize) -> bool {
+        self.heap.delete(id).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+impl FromIterator<usize> for IdHeap {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        IdHeap {
+            heap: MinHeap::build_heap(iter.into_iter().map(|id| (id, id)).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdHeap;
+
+    #[test]
+    fn pops_ids_in_ascending_order() {
+        let scrambled: Vec<usize> = (0..50).map(|i| (i * 31 + 7) % 50).collect();
+
+        let mut h = IdHeap::new();
+        for &id in &scrambled {
+            h.insert(id);
+        }
+        assert!(h.remove(10));
+        assert!(!h.remove(10));
+        assert_eq!(h.peek(), Some(0));
+
+        let popped: Vec<_> = std::iter::from_fn(|| h.pop()).collect();
+        let expected: Vec<_> = (0..50).filter(|&id| id != 10).collect();
+        assert_eq!(popped, expected);
+
+        let mut h: IdHeap = scrambled.into_iter().collect();
+        assert_eq!(h.len(), 50);
+        assert_eq!(h.pop(), Some(0));
+        assert!(h.contains(49) && !h.contains(0));
+    }
+}
@@ -0,0 +1,43 @@
+//! Id allocation for the heaps' `push`.
+//!
+//! Ids are handed out from a counter, and ids that left the heap are reused
+//! first. The free list may hold stale entries (an id released and then
+//! inserted by hand), so `next` skips ids the heap still holds.
+
+#[derive(Clone, Default, Debug)]
+pub(crate) struct IdAllocator {
+    next: usize,
+    free: Vec<usize>,
+}
+
+impl IdAllocator {
+    /// An id for which `in_use` is false.
+    pub(crate) fn next(&mut self, in_use: impl Fn(usize) -> bool) -> usize {
+        while let Some(id) = self.free.pop() {
+            if !in_use(id) {
+                return id;
+            }
+        }
+        // ids inserted by hand may sit above the counter
+        while in_use(self.next) {
+            self.next += 1;
+        }
+        self.next += 1;
+        self.next - 1
+    }
+
+    /// `id` left the heap. Ids the counter hasn't reached yet are left to it,
+    /// so heaps that never `push` don't grow a free list.
+    #[inline(always)]
+    pub(crate) fn release(&mut self, id: usize) {
+        if id < self.next {
+            self.free.push(id);
+        }
+    }
+
+    /// The heap is empty, start over from 0.
+    pub(crate) fn reset(&mut self) {
+        self.next = 0;
+        self.free.clear();
+    }
+}
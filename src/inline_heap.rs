@@ -0,0 +1,228 @@
+use crate::MinHeap;
+
+// a heap for the many tiny, short-lived queues (per-node work lists and the
+// like) where the allocation costs more than the heap operations. up to N
+// items live in an array inside the struct and nothing is allocated; the
+// insert that would make it N + 1 moves everything into a regular MinHeap,
+// which it stays until clear.
+//
+// inline items are unordered and found by scanning, with the slot of the
+// min kept up to date, so get_min is O(1) and the rest O(N). that beats a
+// positions table for the small N this is meant for. a separate type rather
+// than a const parameter on MinHeap, whose storage is shared by every
+// wrapper in the crate
+pub struct InlineMinHeap<K, const N: usize> {
+    storage: Storage<K, N>,
+}
+
+enum Storage<K, const N: usize> {
+    Inline {
+        items: [Option<(usize, K)>; N],
+        len: usize,
+        // slot of the min item, meaningless while len == 0
+        min: usize,
+    },
+    // boxed so the inline case isn't padded out to a whole MinHeap, the
+    // spill allocates anyway
+    Spilled(Box<MinHeap<K>>),
+}
+
+impl<K: PartialOrd + Clone, const N: usize> Default for InlineMinHeap<K, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone, const N: usize> InlineMinHeap<K, N> {
+    pub fn new() -> Self {
+        InlineMinHeap {
+            storage: Storage::Inline {
+                items: std::array::from_fn(|_| None),
+                len: 0,
+                min: 0,
+            },
+        }
+    }
+
+    // whether the items still sit in the inline array
+    pub fn is_inline(&self) -> bool {
+        matches!(self.storage, Storage::Inline { .. })
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { len, .. } => *len,
+            Storage::Spilled(heap) => heap.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // ids must be unique, same as for MinHeap
+    pub fn insert(&mut self, item: (usize, K)) {
+        debug_assert!(!self.contains(item.0), "duplicate id {}", item.0);
+        match &mut self.storage {
+            Storage::Inline { items, len, min } if *len < N => {
+                if *len == 0 || item.1 < Self::key_at(items, *min).1 {
+                    *min = *len;
+                }
+                items[*len] = Some(item);
+                *len += 1;
+            }
+            Storage::Inline { items, len, .. } => {
+                let mut spilled: Vec<_> =
+                    items[..*len].iter_mut().filter_map(Option::take).collect();
+                spilled.push(item);
+                self.storage = Storage::Spilled(Box::new(MinHeap::build_heap(spilled)));
+            }
+            Storage::Spilled(heap) => heap.insert(item),
+        }
+    }
+
+    pub fn get_min(&self) -> Option<&(usize, K)> {
+        match &self.storage {
+            Storage::Inline { len: 0, .. } => None,
+            Storage::Inline { items, min, .. } => Some(Self::key_at(items, *min)),
+            Storage::Spilled(heap) => heap.get_min(),
+        }
+    }
+
+    // owned copy of the min item
+    pub fn peek(&self) -> Option<(usize, K)> {
+        self.get_min().cloned()
+    }
+
+    pub fn delete_min(&mut self) -> Option<(usize, K)> {
+        match &mut self.storage {
+            Storage::Inline { len: 0, .. } => None,
+            Storage::Inline { items, len, min } => {
+                *len -= 1;
+                items.swap(*min, *len);
+                let popped = items[*len].take();
+                *min = Self::min_slot(&items[..*len]);
+                popped
+            }
+            Storage::Spilled(heap) => heap.delete_min(),
+        }
+    }
+
+    // new_key must not be larger than the current key. a no-op for ids that
+    // aren't in the heap
+    pub fn decrease_key(&mut self, id: usize, new_key: K) {
+        match &mut self.storage {
+            Storage::Inline { items, len, min } => {
+                let Some(slot) = Self::slot_of(&items[..*len], id) else {
+                    return;
+                };
+                debug_assert!(
+                    new_key.partial_cmp(&Self::key_at(items, slot).1)
+                        != Some(std::cmp::Ordering::Greater),
+                    "decrease_key: new key is larger than the current one"
+                );
+                if new_key < Self::key_at(items, *min).1 {
+                    *min = slot;
+                }
+                if let Some((_, key)) = &mut items[slot] {
+                    *key = new_key;
+                }
+            }
+            Storage::Spilled(heap) => heap.decrease_key(id, new_key),
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        match &self.storage {
+            Storage::Inline { items, len, .. } => Self::slot_of(&items[..*len], id).is_some(),
+            Storage::Spilled(heap) => heap.contains(id),
+        }
+    }
+
+    // empty the heap and go back to inline storage, dropping any allocation
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn key_at(items: &[Option<(usize, K)>], slot: usize) -> &(usize, K) {
+        items[slot]
+            .as_ref()
+            .expect("inline slots below len are filled")
+    }
+
+    fn slot_of(items: &[Option<(usize, K)>], id: usize) -> Option<usize> {
+        items
+            .iter()
+            .position(|e| e.as_ref().is_some_and(|(i, _)| *i == id))
+    }
+
+    // first slot holding a smallest key, 0 for an empty slice
+    fn min_slot(items: &[Option<(usize, K)>]) -> usize {
+        (1..items.len()).fold(0, |best, slot| {
+            if Self::key_at(items, slot).1 < Self::key_at(items, best).1 {
+                slot
+            } else {
+                best
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineMinHeap;
+
+    #[test]
+    fn stays_inline_up_to_n() {
+        let mut h: InlineMinHeap<i32, 4> = InlineMinHeap::new();
+        for (id, key) in [(0, 40), (1, 10), (2, 30), (3, 20)] {
+            h.insert((id, key));
+        }
+        assert!(h.is_inline());
+        assert_eq!(h.len(), 4);
+        assert_eq!(h.peek(), Some((1, 10)));
+
+        h.decrease_key(2, 5);
+        h.decrease_key(9, 0); // not present
+        assert!(h.contains(2) && !h.contains(9));
+        assert_eq!(h.delete_min(), Some((2, 5)));
+        h.insert((5, 15));
+        assert!(h.is_inline());
+
+        let popped: Vec<_> = std::iter::from_fn(|| h.delete_min()).collect();
+        assert_eq!(popped, vec![(1, 10), (5, 15), (3, 20), (0, 40)]);
+        assert!(h.is_inline());
+        assert_eq!(h.get_min(), None);
+    }
+
+    #[test]
+    fn spills_past_n_and_keeps_order() {
+        let mut h: InlineMinHeap<i32, 3> = InlineMinHeap::new();
+        let keys = [50, 20, 70, 10, 60, 30];
+        for (id, &key) in keys.iter().enumerate() {
+            h.insert((id, key));
+            assert_eq!(h.is_inline(), id < 3);
+        }
+        h.decrease_key(4, 0);
+        assert_eq!(h.len(), 6);
+
+        let popped: Vec<_> = std::iter::from_fn(|| h.delete_min()).collect();
+        assert_eq!(
+            popped,
+            vec![(4, 0), (3, 10), (1, 20), (5, 30), (0, 50), (2, 70)]
+        );
+        assert!(!h.is_inline());
+
+        h.clear();
+        assert!(h.is_inline() && h.is_empty());
+    }
+
+    #[test]
+    fn zero_capacity_spills_on_first_insert() {
+        let mut h: InlineMinHeap<i32, 0> = InlineMinHeap::new();
+        assert_eq!(h.delete_min(), None);
+        h.insert((0, 1));
+        assert!(!h.is_inline());
+        assert_eq!(h.delete_min(), Some((0, 1)));
+    }
+}
@@ -1,7 +1,47 @@
+pub mod algorithms;
+mod borrowed;
+#[cfg(feature = "bounded")]
+mod bounded;
+mod cached_key;
+mod error;
 mod fibonacci_heap;
+mod generational;
+mod id_heap;
+mod ids;
+#[cfg(feature = "inline")]
+mod inline_heap;
+mod metrics;
 mod minheap;
-pub use fibonacci_heap::FibHeap;
-pub use minheap::MinHeap;
+mod minmax_heap;
+mod non_empty;
+mod observer;
+mod op;
+mod order;
+mod sorted_view;
+pub mod stats;
+pub mod sync;
+pub use borrowed::BorrowedKeyHeap;
+#[cfg(feature = "bounded")]
+pub use bounded::Bounded;
+pub use cached_key::CachedKeyHeap;
+pub use error::HeapError;
+pub use fibonacci_heap::{ConsolidatePolicy, FibHeap, FibHeapIter};
+pub use generational::{Handle, TrackedMinHeap};
+pub use id_heap::IdHeap;
+#[cfg(feature = "inline")]
+pub use inline_heap::InlineMinHeap;
+#[cfg(feature = "metrics")]
+pub use metrics::OpCounters;
+pub use minheap::{
+    transfer, DrainSorted, DrainingIter, MinHeap, MinHeapBuilder, MinWatcher, SlotHandle,
+    SortedIter,
+};
+pub use minmax_heap::MinMaxHeap;
+pub use non_empty::NonEmptyMinHeap;
+pub use observer::HeapObserver;
+pub use op::{Op, OpResult};
+pub use order::Comparator;
+pub use sorted_view::SortedView;
 
 #[cfg(test)]
 mod tests {
@@ -29,4 +69,56 @@ mod tests {
         assert_eq!(popped, (1, 5));
         assert_eq!(*f.get_min().unwrap(), (0, 10));
     }
+
+    #[test]
+    fn heaps_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<MinHeap<u32>>();
+        assert_send_sync::<MinHeap<String>>();
+        assert_send_sync::<FibHeap<u32>>();
+        assert_send_sync::<FibHeap<String>>();
+    }
+
+    // Ord std types work as keys as they are, no Copy or wrapper needed
+    #[test]
+    fn ord_std_keys() {
+        use std::cmp::Reverse;
+        use std::time::{Duration, Instant};
+
+        let t0 = Instant::now();
+        let deadlines = [5, 1, 4, 2, 3].map(|s| t0 + Duration::from_secs(s));
+        let mut h = MinHeap::new();
+        let mut f = FibHeap::new();
+        for (id, at) in deadlines.iter().enumerate() {
+            h.insert((id, *at));
+            f.insert((id, *at));
+        }
+        h.decrease_key(0, t0);
+        f.decrease_key(0, t0);
+        let order: Vec<usize> = std::iter::from_fn(|| h.delete_min().map(|e| e.0)).collect();
+        assert_eq!(order, vec![0, 1, 3, 4, 2]);
+        let order: Vec<usize> = std::iter::from_fn(|| f.delete_min().map(|e| e.0)).collect();
+        assert_eq!(order, vec![0, 1, 3, 4, 2]);
+
+        // Reverse turns either heap into a max-heap
+        let mut h = MinHeap::new();
+        let mut f = FibHeap::new();
+        for (id, k) in [7u32, 42, 3, 19].into_iter().enumerate() {
+            h.insert((id, Reverse(k)));
+            f.insert((id, Reverse(k)));
+        }
+        h.decrease_key(2, Reverse(100));
+        f.decrease_key(2, Reverse(100));
+        let keys: Vec<u32> = std::iter::from_fn(|| h.delete_min().map(|e| e.1 .0)).collect();
+        assert_eq!(keys, vec![100, 42, 19, 7]);
+        let keys: Vec<u32> = std::iter::from_fn(|| f.delete_min().map(|e| e.1 .0)).collect();
+        assert_eq!(keys, vec![100, 42, 19, 7]);
+
+        // decrease_key_by only asks for Clone + Sub
+        let mut h = MinHeap::new();
+        h.insert((0, Duration::from_millis(900)));
+        h.insert((1, Duration::from_millis(500)));
+        h.decrease_key_by(0, Duration::from_millis(800));
+        assert_eq!(h.get_min(), Some(&(0, Duration::from_millis(100))));
+    }
 }
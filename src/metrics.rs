@@ -0,0 +1,81 @@
+//! Operation counters behind the `metrics` feature.
+//!
+//! Without the feature `Counter` is zero-sized and `bump` compiles to
+//! nothing, so the heaps can call it unconditionally.
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+// an atomic rather than a Cell so the feature doesn't take Sync away from
+// the heaps. the counts are statistics only, so relaxed ordering will do
+#[derive(Default, Debug)]
+pub(crate) struct Counter(#[cfg(feature = "metrics")] AtomicU64);
+
+impl Clone for Counter {
+    fn clone(&self) -> Self {
+        #[cfg(feature = "metrics")]
+        return Counter(AtomicU64::new(self.get()));
+        #[cfg(not(feature = "metrics"))]
+        Counter()
+    }
+}
+
+impl Counter {
+    #[inline(always)]
+    pub(crate) fn bump(&self) {
+        #[cfg(feature = "metrics")]
+        self.0.fetch_add(1, Relaxed);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn get(&self) -> u64 {
+        self.0.load(Relaxed)
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn reset(&self) {
+        self.0.store(0, Relaxed);
+    }
+}
+
+/// Snapshot of a heap's operation counters, returned by `op_counters`.
+/// Counters a heap doesn't have stay 0.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OpCounters {
+    pub comparisons: u64,
+    /// `FibHeap`: trees linked under another root while consolidating.
+    pub links: u64,
+    /// `FibHeap`: nodes cut from their parent, cascading ones included.
+    pub cuts: u64,
+    /// `FibHeap`: the cuts triggered by an already marked parent.
+    pub cascading_cuts: u64,
+    /// `FibHeap`: consolidation passes.
+    pub consolidations: u64,
+    /// `MinHeap`: single-level swaps made by `bubble_up` / `bubble_down`.
+    pub sift_steps: u64,
+    /// `FibHeap`: times a consolidation buffer had to reallocate, see
+    /// `with_scratch_capacity`.
+    pub scratch_grows: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Counter;
+
+    #[test]
+    fn counter_is_sync_and_clones_its_count() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Counter>();
+
+        #[cfg(feature = "metrics")]
+        {
+            let c = Counter::default();
+            c.bump();
+            c.bump();
+            let copy = c.clone();
+            c.bump();
+            assert_eq!((c.get(), copy.get()), (3, 2));
+        }
+    }
+}
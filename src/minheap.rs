@@ -1,68 +1,528 @@
 use std::cmp::Ordering;
-use std::usize;
+use std::ops::Sub;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+
+use crate::ids::IdAllocator;
+use crate::metrics::Counter;
+#[cfg(feature = "metrics")]
+use crate::metrics::OpCounters;
+use crate::order::{KeyOrder, OrderKind};
+use crate::{Comparator, HeapError, HeapObserver, Op, OpResult, SortedView};
 
 pub struct MinHeap<K> {
     // an entry is an item_id and key tuple
     heap: Vec<(usize, K)>,
     //holds the position/index of an item in the heap
     positions: Vec<usize>,
+    // which entry counts as "smaller", see MinHeapBuilder
+    order: KeyOrder<K>,
+    // when set, every popped key must be >= the previously popped key
+    monotone: bool,
+    last_popped: Option<K>,
+    // key comparisons, only counted with the metrics feature
+    comparisons: Counter,
+    // single-level swaps made while sifting, metrics feature only
+    sift_steps: Counter,
+    // fixed capacity mode for insert_or_evict_max
+    fixed_cap: Option<usize>,
+    // hard limit on len, inserts past it fail instead of evicting
+    max_len: Option<usize>,
+    // id of the current max item if known, see max_slot
+    max_hint: Option<usize>,
+    // operation callbacks, not carried over by clone
+    observer: Option<Box<dyn HeapObserver<K>>>,
+    // lazy deletion mode: delete(id) only tombstones the entry (dead is
+    // indexed by id), delete_min skips dead roots and the array is compacted
+    // once half of it is dead. the root is never dead
+    lazy: bool,
+    dead: Vec<bool>,
+    dead_count: usize,
+    // every popped entry while enabled, for diffing runs
+    pop_log: Option<Vec<(usize, K)>>,
+    // a try_ method ran into an incomparable key, see try_get_min
+    poisoned: bool,
+    // ids handed out by push
+    ids: IdAllocator,
+    // slots of the live SlotHandles by id, rewritten by the sifts whenever
+    // they move one of those ids. not carried over by clone
+    handles: Vec<Option<Arc<AtomicUsize>>>,
+}
+
+impl<K: Clone> Clone for MinHeap<K> {
+    fn clone(&self) -> Self {
+        MinHeap {
+            heap: self.heap.clone(),
+            positions: self.positions.clone(),
+            order: self.order.clone(),
+            monotone: self.monotone,
+            last_popped: self.last_popped.clone(),
+            comparisons: self.comparisons.clone(),
+            sift_steps: self.sift_steps.clone(),
+            fixed_cap: self.fixed_cap,
+            max_len: self.max_len,
+            max_hint: self.max_hint,
+            observer: None,
+            lazy: self.lazy,
+            dead: self.dead.clone(),
+            dead_count: self.dead_count,
+            pop_log: self.pop_log.clone(),
+            poisoned: self.poisoned,
+            ids: self.ids.clone(),
+            handles: Vec::new(),
+        }
+    }
+
+    // reuse our own allocations instead of allocating fresh vectors
+    fn clone_from(&mut self, source: &Self) {
+        self.heap.clone_from(&source.heap);
+        self.positions.clone_from(&source.positions);
+        self.order = source.order.clone();
+        self.monotone = source.monotone;
+        self.last_popped.clone_from(&source.last_popped);
+        self.fixed_cap = source.fixed_cap;
+        self.max_len = source.max_len;
+        self.max_hint = source.max_hint;
+        self.lazy = source.lazy;
+        self.dead.clone_from(&source.dead);
+        self.dead_count = source.dead_count;
+        self.pop_log.clone_from(&source.pop_log);
+        self.poisoned = source.poisoned;
+        self.ids.clone_from(&source.ids);
+        self.handles.clear();
+    }
+}
+
+// equal when both hold the same (id, key) entries, whatever the layout.
+// compares id-sorted copies, O(n log n)
+impl<K: PartialEq> PartialEq for MinHeap<K> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.heap.len() - self.dead_count != other.heap.len() - other.dead_count {
+            return false;
+        }
+        fn sorted<K>(h: &MinHeap<K>) -> Vec<&(usize, K)> {
+            let mut entries: Vec<_> = h.live_entries().collect();
+            entries.sort_unstable_by_key(|e| e.0);
+            entries
+        }
+        sorted(self) == sorted(other)
+    }
+}
+
+impl<K: PartialOrd + Clone> Default for MinHeap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<K: PartialOrd + Copy> MinHeap<K> {
+impl<K: PartialOrd + Clone> MinHeap<K> {
     // New minheap
     pub fn new() -> Self {
         MinHeap {
             heap: Vec::new(),
             positions: Vec::new(),
+            order: KeyOrder::natural(),
+            monotone: false,
+            last_popped: None,
+            comparisons: Counter::default(),
+            sift_steps: Counter::default(),
+            fixed_cap: None,
+            max_len: None,
+            max_hint: None,
+            observer: None,
+            lazy: false,
+            dead: Vec::new(),
+            dead_count: 0,
+            pop_log: None,
+            poisoned: false,
+            ids: IdAllocator::default(),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn builder() -> MinHeapBuilder<K> {
+        MinHeapBuilder::new()
+    }
+
+    // minheap of whole values ordered by a projection, e.g. a struct field.
+    // key_fn runs on both sides of every comparison (about 2 log n calls
+    // per insert or pop) and nothing is cached, so keep it cheap. for an
+    // expensive projection store (key, value) tuples instead, which
+    // compare lexicographically and compute the key once
+    pub fn new_by_key<P, F>(key_fn: F) -> Self
+    where
+        P: PartialOrd,
+        F: Fn(&K) -> P + Send + Sync + 'static,
+    {
+        let cmp = move |a: &K, b: &K| {
+            key_fn(a)
+                .partial_cmp(&key_fn(b))
+                .expect("new_by_key: incomparable keys")
+        };
+        MinHeap {
+            order: KeyOrder {
+                kind: OrderKind::Custom(Arc::new(cmp)),
+                stable: false,
+            },
+            ..Self::new()
+        }
+    }
+
+    // minheap that reports every insert, delete_min and decrease_key
+    pub fn with_observer(obs: impl HeapObserver<K> + 'static) -> Self {
+        MinHeap {
+            observer: Some(Box::new(obs)),
+            ..Self::new()
+        }
+    }
+
+    // minheap that holds at most cap items, see insert_or_evict_max
+    pub fn with_fixed_capacity(cap: usize) -> Self {
+        MinHeap {
+            heap: Vec::with_capacity(cap),
+            fixed_cap: Some(cap),
+            ..Self::new()
+        }
+    }
+
+    // minheap that never grows past cap items: try_insert fails with
+    // CapacityExceeded and insert panics once len() == cap. unlike
+    // with_fixed_capacity nothing is evicted to make room
+    pub fn with_max_len(cap: usize) -> Self {
+        MinHeap {
+            max_len: Some(cap),
+            ..Self::new()
+        }
+    }
+
+    // minheap that panics if a pop ever yields a key smaller than the
+    // previous pop, e.g. dijkstra with a negative edge
+    pub fn new_monotone() -> Self {
+        MinHeap {
+            monotone: true,
+            ..Self::new()
+        }
+    }
+
+    // minheap whose delete(id) tombstones instead of restructuring, see
+    // the lazy field
+    pub fn new_lazy() -> Self {
+        MinHeap {
+            lazy: true,
+            ..Self::new()
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.heap.is_empty()
+        self.len() == 0
     }
 
+    // live entries only, tombstones don't count
     pub fn len(&self) -> usize {
-        self.heap.len()
+        self.heap.len() - self.dead_count
     }
 
+    // O(len): only live ids are reset, both vecs keep their capacity
     pub fn clear(&mut self) {
         // For every (id, key) we’ve stored, mark its position back to “not in heap”
+        // (tombstones are still in the array, so their flags go here too)
         for &(id, _) in &self.heap {
             self.positions[id] = usize::MAX;
+            if let Some(dead) = self.dead.get_mut(id) {
+                *dead = false;
+            }
         }
         // Then clear the underlying vector
         self.heap.clear();
+        self.last_popped = None;
+        self.max_hint = None;
+        self.poisoned = false;
+        self.ids.reset();
+        self.dead_count = 0;
     }
 
     // build min heap from an unsorted vec of (item_id, key)
+    // panics on duplicate ids or incomparable keys, see try_build_heap
     pub fn build_heap(items: Vec<(usize, K)>) -> Self {
+        match Self::try_build_heap(items) {
+            Ok(min_heap) => min_heap,
+            Err(e) => panic!("build_heap: invalid input ({:?})", e),
+        }
+    }
+
+    // clear and refill with items, keeping the existing allocations of the
+    // array and positions. O(n) like build_heap, which it mirrors: panics
+    // on duplicate ids or incomparable keys
+    pub fn reset_to(&mut self, items: Vec<(usize, K)>) {
+        self.clear();
+        self.heap.extend(items);
+        for &(id, _) in &self.heap {
+            assert!(id != usize::MAX, "id too large: {id}");
+            if id >= self.positions.len() {
+                self.positions.resize(id + 1, usize::MAX);
+            }
+            assert_eq!(
+                self.positions[id],
+                usize::MAX,
+                "reset_to: duplicate id {id}"
+            );
+            self.positions[id] = 0;
+        }
+        self.heapify();
+    }
+
+    // union with other. an id in both heaps keeps whichever key comes
+    // first in self's ordering, ids only in other are added. keys are set
+    // in place and the order restored with one heapify, O(n + m)
+    pub fn merge_keep_min(&mut self, mut other: MinHeap<K>) {
+        self.compact();
+        other.compact();
+        for (id, key) in other.heap {
+            match self.slot_of(id) {
+                Some(p) => {
+                    if self.order.cmp_keys(&key, &self.heap[p].1) == Some(Ordering::Less) {
+                        if let Some(obs) = self.observer.as_mut() {
+                            obs.on_decrease_key(id, &key);
+                        }
+                        self.heap[p].1 = key;
+                    }
+                }
+                None => {
+                    if id >= self.positions.len() {
+                        self.positions.resize(id + 1, usize::MAX);
+                    }
+                    if let Some(obs) = self.observer.as_mut() {
+                        obs.on_insert(id, &key);
+                    }
+                    self.positions[id] = self.heap.len();
+                    self.heap.push((id, key));
+                }
+            }
+        }
+        self.heapify();
+    }
+
+    // take the heap apart into (heap array, positions) for persisting the
+    // exact layout. tombstones of a lazy heap are compacted away first;
+    // ordering and mode settings are not part of the parts
+    pub fn into_raw_parts(mut self) -> (Vec<(usize, K)>, Vec<usize>) {
+        self.compact();
+        (self.heap, self.positions)
+    }
+
+    /// Reassemble a naturally ordered heap from `into_raw_parts` output
+    /// without checking it.
+    ///
+    /// # Safety
+    ///
+    /// `heap` must be in heap order and `positions` its exact inverse
+    /// (`positions[id] == idx` for every entry, `usize::MAX` for every other
+    /// id). Nothing is checked; a bad layout makes later operations panic or
+    /// silently return wrong results. Use `try_from_raw_parts` otherwise.
+    pub unsafe fn from_raw_parts(heap: Vec<(usize, K)>, positions: Vec<usize>) -> Self {
+        MinHeap {
+            heap,
+            positions,
+            ..Self::new()
+        }
+    }
+
+    // from_raw_parts that checks the layout first, O(n + ids)
+    pub fn try_from_raw_parts(
+        heap: Vec<(usize, K)>,
+        positions: Vec<usize>,
+    ) -> Result<Self, HeapError> {
+        for (idx, (id, key)) in heap.iter().enumerate() {
+            if positions.get(*id) != Some(&idx) {
+                return Err(HeapError::InvalidLayout(
+                    "positions doesn't point back at every entry",
+                ));
+            }
+            if idx > 0 {
+                match heap[(idx - 1) / 2].1.partial_cmp(key) {
+                    Some(Ordering::Greater) => {
+                        return Err(HeapError::InvalidLayout("heap order is violated"))
+                    }
+                    Some(_) => {}
+                    None => return Err(HeapError::Incomparable),
+                }
+            }
+        }
+        let mapped = positions.iter().filter(|&&p| p != usize::MAX).count();
+        if mapped != heap.len() {
+            return Err(HeapError::InvalidLayout(
+                "positions maps ids that are not in the heap",
+            ));
+        }
+        // SAFETY: the layout was just checked
+        Ok(unsafe { Self::from_raw_parts(heap, positions) })
+    }
+
+    // build a heap from bare keys, key i gets id i, O(n)
+    pub fn from_keys(keys: Vec<K>) -> Self {
+        Self::build_heap(keys.into_iter().enumerate().collect())
+    }
+
+    // build_heap that validates its input instead of trusting it
+    pub fn try_build_heap(items: Vec<(usize, K)>) -> Result<Self, HeapError> {
         let heap = items;
 
         // find size of positions array
         let pos_max = heap.iter().map(|(id, _)| *id).max().unwrap_or(0);
+        if pos_max == usize::MAX {
+            return Err(HeapError::IdTooLarge(pos_max));
+        }
 
         let mut positions = vec![usize::MAX; pos_max + 1];
 
         // create positions so that position[id] is the items index in the heap
         for (idx, (id, _)) in heap.iter().enumerate() {
+            if positions[*id] != usize::MAX {
+                return Err(HeapError::DuplicateId(*id));
+            }
             positions[*id] = idx;
         }
 
         // create a MinHeap instance
-        let mut min_heap = MinHeap { heap, positions };
+        let mut min_heap = MinHeap {
+            heap,
+            positions,
+            ..Self::new()
+        };
 
-        let n = min_heap.heap.len();
-        if n > 1 {
-            for i in (0..=(n / 2 - 1)).rev() {
-                min_heap.bubble_down(i);
+        // input that is already sorted is already a heap: one O(n) scan
+        // (stopping at the first descent) can save the whole sift sweep
+        if !min_heap.is_sorted() {
+            min_heap.try_sift_all()?;
+        }
+
+        Ok(min_heap)
+    }
+
+    // move every entry whose key matches pred into a new heap, O(n)
+    pub fn split_off(&mut self, pred: impl Fn(&K) -> bool) -> MinHeap<K> {
+        self.compact();
+        let mut moved = Vec::new();
+        let mut i = 0;
+        while i < self.heap.len() {
+            if pred(&self.heap[i].1) {
+                let item = self.heap.swap_remove(i);
+                self.positions[item.0] = usize::MAX;
+                self.ids.release(item.0);
+                moved.push(item);
+            } else {
+                i += 1;
+            }
+        }
+
+        self.heapify();
+        MinHeap::build_heap(moved)
+    }
+
+    // remove and return every entry pred accepts, in no particular order.
+    // the rest is re-heapified once, O(n)
+    pub fn drain_filter<F: FnMut(usize, &K) -> bool>(&mut self, mut pred: F) -> Vec<(usize, K)> {
+        self.compact();
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while i < self.heap.len() {
+            let (id, key) = &self.heap[i];
+            if pred(*id, key) {
+                let item = self.heap.swap_remove(i);
+                self.positions[item.0] = usize::MAX;
+                self.ids.release(item.0);
+                removed.push(item);
+            } else {
+                i += 1;
+            }
+        }
+
+        if !removed.is_empty() {
+            self.heapify();
+        }
+        removed
+    }
+
+    // insert every item and return the resulting min. positions grows once;
+    // a batch that is large next to the heap is appended and heapified in
+    // O(n + k) instead of k sifts of O(log n)
+    pub fn insert_batch(&mut self, items: &[(usize, K)]) -> Option<(usize, K)> {
+        let Some(max_id) = items.iter().map(|e| e.0).max() else {
+            return self.peek();
+        };
+        assert!(max_id != usize::MAX, "id too large: {max_id}");
+        assert!(
+            self.max_len.is_none_or(|m| self.len() + items.len() <= m),
+            "capacity exceeded: batch doesn't fit in max_len"
+        );
+        if max_id >= self.positions.len() {
+            self.positions.resize(max_id + 1, usize::MAX);
+        }
+        self.heap.reserve(items.len());
+
+        let total = self.len() + items.len();
+        if items.len() * total.max(2).ilog2() as usize > total {
+            // tombstoned ids may be reused by the batch
+            self.compact();
+            for item in items {
+                if let Some(obs) = self.observer.as_mut() {
+                    obs.on_insert(item.0, &item.1);
+                }
+                self.heap.push(item.clone());
             }
+            self.heapify();
+        } else {
+            for item in items {
+                self.insert(item.clone());
+            }
+        }
+        self.peek()
+    }
+
+    // insert that checks its input instead of panicking or corrupting the
+    // heap. nothing is modified on error
+    pub fn try_insert(&mut self, item: (usize, K)) -> Result<(), HeapError> {
+        let id = item.0;
+        if id == usize::MAX {
+            return Err(HeapError::IdTooLarge(id));
         }
+        if self.contains(id) {
+            return Err(HeapError::DuplicateId(id));
+        }
+        if self.max_len.is_some_and(|m| self.len() >= m) {
+            return Err(HeapError::CapacityExceeded);
+        }
+        if self.order.cmp_keys(&item.1, &item.1).is_none() {
+            return Err(HeapError::Incomparable);
+        }
+        self.insert(item);
+        Ok(())
+    }
 
-        min_heap
+    // insert under the next free id and return it, for callers that don't
+    // manage ids. ids that left the heap are reused before new ones
+    pub fn push(&mut self, key: K) -> usize {
+        let mut ids = std::mem::take(&mut self.ids);
+        let id = ids.next(|id| self.contains(id));
+        self.ids = ids;
+        self.insert((id, key));
+        id
     }
 
     // inserts a value and moves it to the right place
     pub fn insert(&mut self, item: (usize, K)) {
+        assert!(item.0 != usize::MAX, "id too large: {}", item.0);
+        assert!(
+            self.max_len.is_none_or(|m| self.len() < m),
+            "capacity exceeded: heap is at max_len"
+        );
+        // a tombstone for this id has to go before the id can be reused
+        if self.is_dead(item.0) {
+            self.dead[item.0] = false;
+            self.dead_count -= 1;
+            self.remove_at(self.positions[item.0]);
+        }
+
         // add item to the heap
         self.heap.push(item);
 
@@ -79,8 +539,97 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
         // the item (id) is at heap index (idx)
         self.positions[id] = idx;
 
+        if let Some(obs) = self.observer.as_mut() {
+            obs.on_insert(id, &self.heap[idx].1);
+        }
+
         // recreate heap order
-        self.bubble_up(idx)
+        self.bubble_up(idx);
+
+        // a new overall max sits at the leaf it was pushed to
+        if let Some(max_slot) = self.max_hint.and_then(|h| self.slot_of(h)) {
+            if self.cmp_slots(idx, max_slot) == Ok(Ordering::Greater) {
+                self.max_hint = Some(id);
+            }
+        }
+    }
+
+    // insert and return a handle carrying the item's slot. the heap keeps
+    // that slot current through bubble_up/bubble_down, see SlotHandle
+    pub fn insert_handle(&mut self, item: (usize, K)) -> SlotHandle {
+        let id = item.0;
+        self.insert(item);
+        let slot = Arc::new(AtomicUsize::new(self.positions[id]));
+        if id >= self.handles.len() {
+            self.handles.resize(id + 1, None);
+        }
+        self.handles[id] = Some(Arc::clone(&slot));
+        SlotHandle { id, slot }
+    }
+
+    // decrease_key through a handle: the key is written at the handle's slot
+    // without going through positions, and there is no comparison to pick a
+    // sift direction, the item only ever bubbles up. moves other than sifts
+    // (removals, heapify, sorting) don't patch handles, so a slot that no
+    // longer holds the id falls back to positions and is refreshed. a no-op
+    // once the item has left the heap
+    pub fn decrease_key_handle(&mut self, h: &SlotHandle, new_key: K) {
+        let cached = h.slot.load(atomic::Ordering::Relaxed);
+        let slot = match self.heap.get(cached) {
+            Some((id, _)) if *id == h.id && !self.is_dead(h.id) => cached,
+            _ => match self.slot_of(h.id) {
+                Some(p) => {
+                    h.slot.store(p, atomic::Ordering::Relaxed);
+                    p
+                }
+                None => return,
+            },
+        };
+        debug_assert!(
+            self.order.cmp_keys(&new_key, &self.heap[slot].1) != Some(Ordering::Greater),
+            "decrease_key_handle: new key orders after the current one"
+        );
+        if self.max_hint == Some(h.id) {
+            self.max_hint = None;
+        }
+        self.heap[slot].1 = new_key;
+        if let Some(obs) = self.observer.as_mut() {
+            obs.on_decrease_key(h.id, &self.heap[slot].1);
+        }
+        self.bubble_up(slot);
+    }
+
+    // tell id's handle, if it has one, that the item now sits at slot
+    fn patch_handle(&self, id: usize, slot: usize) {
+        if let Some(Some(h)) = self.handles.get(id) {
+            h.store(slot, atomic::Ordering::Relaxed);
+        }
+    }
+
+    // in fixed capacity mode, when the heap is full, drop the largest item
+    // (which may be the new one) and return it. the max is found with an
+    // O(n) scan of the leaves and cached until it changes
+    pub fn insert_or_evict_max(&mut self, item: (usize, K)) -> Option<(usize, K)> {
+        match self.fixed_cap {
+            Some(cap) if self.len() >= cap => {
+                self.compact();
+                let max_slot = match self.max_slot() {
+                    Some(slot) => slot,
+                    None => return Some(item), // capacity 0
+                };
+                if self.order.cmp_keys(&item.1, &self.heap[max_slot].1) != Some(Ordering::Less) {
+                    return Some(item);
+                }
+                let max_id = self.heap[max_slot].0;
+                let evicted = self.delete(max_id);
+                self.insert(item);
+                evicted
+            }
+            _ => {
+                self.insert(item);
+                None
+            }
+        }
     }
 
     pub fn delete_min(&mut self) -> Option<(usize, K)> {
@@ -102,6 +651,10 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
         let (min_id, min_key) = self.heap.pop().unwrap();
 
         self.positions[min_id] = usize::MAX;
+        self.ids.release(min_id);
+        if self.max_hint == Some(min_id) {
+            self.max_hint = None;
+        }
 
         if !self.heap.is_empty() {
             let root_id = self.heap[0].0;
@@ -109,13 +662,354 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
 
             self.bubble_down(0);
         }
+        self.purge_top();
+
+        self.note_pop(min_id, &min_key);
+        if let Some(obs) = self.observer.as_mut() {
+            obs.on_delete_min(min_id, &min_key);
+        }
+
+        Some((min_id, min_key))
+    }
+
+    // delete_min that reports incomparable keys instead of panicking. the
+    // sift is replayed read-only first, so on error nothing has changed.
+    // tombstones of a lazy heap are compacted up front, see try_compact
+    pub fn try_delete_min(&mut self) -> Result<Option<(usize, K)>, HeapError> {
+        self.try_compact().map_err(|e| self.poison(e))?;
+        let n = self.heap.len();
+        if n > 1 {
+            let checked = self.check_sift_down(&self.heap[n - 1], 0, n - 1);
+            checked.map_err(|e| self.poison(e))?;
+        }
+        Ok(self.delete_min())
+    }
+
+    // run one Op. ops that would panic or misbehave (duplicate id, missing
+    // id, a key that doesn't decrease, a NaN) return Failed instead
+    pub fn apply(&mut self, op: Op<K>) -> OpResult<K> {
+        match op {
+            Op::Insert(id, key) => match self.try_insert((id, key)) {
+                Ok(()) => OpResult::Done,
+                Err(e) => OpResult::Failed(e),
+            },
+            Op::DeleteMin => OpResult::Popped(self.delete_min()),
+            Op::DecreaseKey(id, key) => match self.try_decrease_key(id, key) {
+                Ok(()) => OpResult::Done,
+                Err(e) => OpResult::Failed(e),
+            },
+            Op::Delete(id) => match self.delete(id) {
+                Some(item) => OpResult::Deleted(item),
+                None => OpResult::Failed(HeapError::NotPresent(id)),
+            },
+            Op::Clear => {
+                self.clear();
+                OpResult::Done
+            }
+        }
+    }
+
+    // delete_min writing into a reused slot: true and out overwritten, or
+    // false with out untouched when the heap is empty
+    pub fn delete_min_into(&mut self, out: &mut (usize, K)) -> bool {
+        match self.delete_min() {
+            Some(item) => {
+                *out = item;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // remove the largest item. finding it is an O(n/2) leaf scan, but the
+    // result is cached until something could have changed the max, so
+    // repeated calls only pay for the scans they invalidated
+    pub fn delete_max(&mut self) -> Option<(usize, K)> {
+        self.compact();
+        let slot = self.max_slot()?;
+        let removed = self.remove_at(slot);
+        if let Some(obs) = self.observer.as_mut() {
+            obs.on_delete(removed.0, &removed.1);
+        }
+        Some(removed)
+    }
+
+    // pop the minimum plus every following entry whose key ties with it,
+    // stopping at the first strictly larger key
+    pub fn delete_min_group(&mut self) -> Vec<(usize, K)> {
+        let first = match self.delete_min() {
+            Some(item) => item,
+            None => return Vec::new(),
+        };
+        let mut group = vec![first];
+        while let Some((_, key)) = self.get_min() {
+            self.comparisons.bump();
+            if self.order.cmp_keys(&group[0].1, key) != Some(Ordering::Equal) {
+                break;
+            }
+            group.push(self.delete_min().unwrap());
+        }
+        group
+    }
+
+    // pop while pred holds for the current minimum, leaving the first key
+    // that fails it in place. the batch comes out in pop order
+    pub fn drain_while<F: FnMut(&K) -> bool>(&mut self, mut pred: F) -> Vec<(usize, K)> {
+        let mut out = Vec::new();
+        while let Some((_, key)) = self.get_min() {
+            if !pred(key) {
+                break;
+            }
+            out.push(self.delete_min().unwrap());
+        }
+        out
+    }
+
+    // remove and return the k smallest items in ascending order.
+    // for k >= len/2 it is cheaper to sort the whole array once: the sorted
+    // remainder is already a valid heap, so only positions need fixing.
+    // below that crossover k single pops (O(k log n)) win.
+    pub fn pop_k(&mut self, k: usize) -> Vec<(usize, K)> {
+        self.compact();
+        let k = k.min(self.heap.len());
+        if k == 0 {
+            return Vec::new();
+        }
+        if k < self.heap.len() / 2 {
+            return (0..k).filter_map(|_| self.delete_min()).collect();
+        }
+
+        self.sort_entries();
+        let rest = self.heap.split_off(k);
+        let popped = std::mem::replace(&mut self.heap, rest);
+
+        for (id, _) in &popped {
+            self.positions[*id] = usize::MAX;
+            self.ids.release(*id);
+        }
+        for (idx, (id, _)) in self.heap.iter().enumerate() {
+            self.positions[*id] = idx;
+        }
+        for (id, key) in &popped {
+            self.note_pop(*id, key);
+            if let Some(obs) = self.observer.as_mut() {
+                obs.on_delete_min(*id, key);
+            }
+        }
+        popped
+    }
+
+    // empty the heap, returning everything in pop order (as defined by the
+    // active ordering). one sort instead of n delete_mins
+    pub fn pop_all(&mut self) -> Vec<(usize, K)> {
+        self.pop_k(self.heap.len())
+    }
+
+    // lazily pop entries in pop order. whatever isn't consumed is removed
+    // when the iterator is dropped
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, K> {
+        DrainSorted { heap: self }
+    }
+
+    // read entries in pop order without touching the heap. a small heap of
+    // array slots holds the frontier: popping a slot pushes its two
+    // children, so taking the first k costs O(k log k) and nothing more
+    pub fn iter_sorted(&self) -> SortedIter<'_, K> {
+        SortedIter {
+            heap: self,
+            frontier: if self.heap.is_empty() {
+                vec![]
+            } else {
+                vec![0]
+            },
+        }
+    }
+
+    // consume the heap popping in order, and give memory back on the way:
+    // the backing array is shrunk to fit each time its length falls to a
+    // power of two, so peak usage halves repeatedly as it drains
+    pub fn into_draining_iter(mut self) -> DrainingIter<K> {
+        self.compact();
+        DrainingIter { heap: self }
+    }
+
+    // copy of the live entries in internal heap order
+    pub fn to_vec(&self) -> Vec<(usize, K)> {
+        if self.dead_count == 0 {
+            return self.heap.clone();
+        }
+        self.live_entries().cloned().collect()
+    }
+
+    // the backing array itself, zero-copy. this is the internal heap layout:
+    // the min is at index 0 but the rest is NOT sorted. a lazy heap also
+    // exposes its tombstones here until the next compaction
+    pub fn as_slice(&self) -> &[(usize, K)] {
+        &self.heap
+    }
+
+    // the backing array cut into tree levels: level L is the slice
+    // [2^L - 1, 2^(L+1) - 1), the last one clamped to the array's end.
+    // same raw layout as as_slice, tombstones included
+    pub fn level_order(&self) -> impl Iterator<Item = (usize, &[(usize, K)])> {
+        let n = self.heap.len();
+        (0..usize::BITS as usize)
+            .map(|level| (level, (1usize << level) - 1))
+            .take_while(move |&(_, start)| start < n)
+            .map(move |(level, start)| {
+                let end = (2 * start + 1).min(n);
+                (level, &self.heap[start..end])
+            })
+    }
+
+    // raw access to the key of id. heap order is NOT restored: follow a
+    // batch of edits with one heapify(), or use change_key for a single one
+    pub fn key_mut(&mut self, id: usize) -> Option<&mut K> {
+        let slot = self.slot_of(id)?;
+        self.max_hint = None;
+        Some(&mut self.heap[slot].1)
+    }
+
+    // replace every key with f(key) and re-heapify, O(n). f doesn't have to
+    // be monotone since the whole array is rebuilt; ids keep their entries
+    pub fn map_keys<F: Fn(&K) -> K>(&mut self, f: F) {
+        self.compact();
+        for (_, key) in self.heap.iter_mut() {
+            *key = f(key);
+        }
+        self.heapify();
+    }
+
+    // switch to a new comparator and re-heapify everything, O(n). later pops
+    // follow cmp; the monotone check restarts since the old order no
+    // longer applies
+    pub fn reorder_by<F>(&mut self, cmp: F)
+    where
+        F: Fn(&K, &K) -> Ordering + Send + Sync + 'static,
+    {
+        self.order.kind = OrderKind::Custom(Arc::new(cmp));
+        self.last_popped = None;
+        self.heapify();
+    }
+
+    // entries with lo <= key < hi, in internal heap order; a heap can't
+    // narrow this down so it's a full O(n) scan. lo > hi yields nothing
+    pub fn range(&self, lo: K, hi: K) -> impl Iterator<Item = &(usize, K)> {
+        self.live_entries()
+            .filter(move |(_, k)| *k >= lo && *k < hi)
+    }
+
+    // consume the heap and return its entries in ascending key order
+    pub fn into_sorted_vec(mut self) -> Vec<(usize, K)> {
+        self.compact();
+        self.sort_entries();
+        self.heap
+    }
+
+    // consume the heap into a read-only view sorted once in heap order, for
+    // build-then-query use: binary search and both ends without popping
+    pub fn freeze(self) -> SortedView<K> {
+        SortedView::from_sorted(self.into_sorted_vec())
+    }
 
-        return Some((min_id, min_key));
+    // consume the heap and return its entries in descending key order.
+    // this is plain in-place heapsort: moving each min behind the shrinking
+    // heap leaves the array largest-first, no reverse needed
+    pub fn into_sorted_vec_desc(mut self) -> Vec<(usize, K)> {
+        self.compact();
+        let mut end = self.heap.len();
+        while end > 1 {
+            end -= 1;
+            self.heap.swap(0, end);
+            self.try_bubble_down(0, end).unwrap();
+        }
+        self.heap
     }
 
     pub fn get_min(&self) -> Option<&(usize, K)> {
         // return min item
-        self.heap.get(0)
+        self.min_slot().map(|slot| &self.heap[slot])
+    }
+
+    // the one place that says where the min lives, for every mode. every
+    // mutation ends with heap order restored and, in lazy mode, purge_top
+    // having cleared tombstones off the root, so it is always slot 0
+    fn min_slot(&self) -> Option<usize> {
+        debug_assert!(self.heap.first().is_none_or(|(id, _)| !self.is_dead(*id)));
+        if self.heap.is_empty() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+
+    // read the min by reference, nothing is cloned
+    pub fn with_min<R>(&self, f: impl FnOnce(usize, &K) -> R) -> Option<R> {
+        self.get_min().map(|(id, key)| f(*id, key))
+    }
+
+    // run f on the min while it is still in place, then pop it
+    pub fn pop_and<R>(&mut self, f: impl FnOnce(usize, &K) -> R) -> Option<R> {
+        let out = self.with_min(f)?;
+        self.delete_min();
+        Some(out)
+    }
+
+    // the min and the second-smallest entry. the runner-up of a binary heap
+    // is always the smaller of the root's children, so this is O(1); in lazy
+    // mode a tombstoned child is looked through to its live descendants.
+    // (min, None) for a single item, (None, None) when empty
+    #[allow(clippy::type_complexity)] // a plain pair reads better than an alias
+    pub fn peek_two(&self) -> (Option<(usize, K)>, Option<(usize, K)>) {
+        let Some(root) = self.min_slot() else {
+            return (None, None);
+        };
+        let mut second: Option<usize> = None;
+        let mut stack = vec![1, 2];
+        while let Some(i) = stack.pop() {
+            if i >= self.heap.len() {
+                continue;
+            }
+            if self.is_dead(self.heap[i].0) {
+                stack.extend([2 * i + 1, 2 * i + 2]);
+            } else if second.is_none_or(|s| self.cmp_slots(i, s) == Ok(Ordering::Less)) {
+                second = Some(i);
+            }
+        }
+        (
+            Some(self.heap[root].clone()),
+            second.map(|s| self.heap[s].clone()),
+        )
+    }
+
+    // get_min that fails once a try_ method has hit an incomparable key
+    // (e.g. a NaN), so a poisoned heap isn't mistaken for an empty one.
+    // clear resets it, rebuild too unless a key is still incomparable
+    pub fn try_get_min(&self) -> Result<Option<&(usize, K)>, HeapError> {
+        if self.poisoned {
+            return Err(HeapError::Incomparable);
+        }
+        Ok(self.get_min())
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    // owned copy of the min item
+    pub fn peek(&self) -> Option<(usize, K)> {
+        self.get_min().cloned()
+    }
+
+    // insert and return the (possibly new) min item
+    pub fn insert_observing(&mut self, item: (usize, K)) -> Option<(usize, K)> {
+        self.insert(item);
+        self.peek()
+    }
+
+    // feed items from an iterator into the heap, yielding (inserted, current_min)
+    pub fn watch<I: Iterator<Item = (usize, K)>>(&mut self, items: I) -> MinWatcher<'_, K, I> {
+        MinWatcher { heap: self, items }
     }
 
     // bubble up an item
@@ -124,20 +1018,20 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
         while index > 0 {
             let parent = (index - 1) / 2;
 
-            if self.heap[index]
-                .1
-                .partial_cmp(&self.heap[parent].1)
-                .unwrap()
-                == Ordering::Less
-            {
+            if self.cmp_slots(index, parent).unwrap() == Ordering::Less {
                 // swap child and parent
                 self.heap.swap(index, parent);
+                self.sift_steps.bump();
 
                 // updatre positions for child and parent
                 let child_id = self.heap[index].0;
                 let parent_id = self.heap[parent].0;
                 self.positions[child_id] = index;
                 self.positions[parent_id] = parent;
+                if !self.handles.is_empty() {
+                    self.patch_handle(child_id, index);
+                    self.patch_handle(parent_id, parent);
+                }
 
                 //update parent
                 index = parent;
@@ -148,9 +1042,13 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
     }
 
     // bubble an item down
-    pub fn bubble_down(&mut self, mut index: usize) {
-        let heap_len = self.heap.len();
+    pub fn bubble_down(&mut self, index: usize) {
+        self.try_bubble_down(index, self.heap.len()).unwrap()
+    }
 
+    // bubble_down within heap[..heap_len] that reports incomparable keys
+    // instead of panicking
+    fn try_bubble_down(&mut self, mut index: usize, heap_len: usize) -> Result<(), HeapError> {
         loop {
             let left_child = (2 * index) + 1;
             let right_child = (2 * index) + 2;
@@ -160,146 +1058,2383 @@ impl<K: PartialOrd + Copy> MinHeap<K> {
                 break;
             }
             // check which child is smaller
-            let smaller_child: usize;
-            if right_child < heap_len
-                && self.heap[right_child]
-                    .1
-                    .partial_cmp(&self.heap[left_child].1)
-                    .unwrap()
-                    == Ordering::Less
+            let smaller_child = if right_child < heap_len
+                && self.cmp_slots(right_child, left_child)? == Ordering::Less
             {
-                smaller_child = right_child;
+                right_child
             } else {
-                smaller_child = left_child;
-            }
+                left_child
+            };
 
             // if the smallest child is smaller than the current swap
-            if self.heap[smaller_child]
-                .1
-                .partial_cmp(&self.heap[index].1)
-                .unwrap()
-                == Ordering::Less
-            {
+            if self.cmp_slots(smaller_child, index)? == Ordering::Less {
                 let child_id = self.heap[smaller_child].0;
                 let parent_id = self.heap[index].0;
 
                 self.heap.swap(smaller_child, index);
+                self.sift_steps.bump();
 
                 self.positions[parent_id] = smaller_child;
                 self.positions[child_id] = index;
+                if !self.handles.is_empty() {
+                    self.patch_handle(parent_id, smaller_child);
+                    self.patch_handle(child_id, index);
+                }
 
                 index = smaller_child;
             } else {
                 break;
             }
         }
+        Ok(())
     }
 
-    pub fn decrease_key(&mut self, id: usize, new_key: K) {
-        let pos_id = self.positions[id];
-        self.heap[pos_id].1 = new_key;
-        self.bubble_up(pos_id);
+    // sort the backing array ascending by key, positions are left stale
+    fn sort_entries(&mut self) {
+        let comparisons = &self.comparisons;
+        let order = &self.order;
+        self.heap.sort_by(|a, b| {
+            comparisons.bump();
+            order.cmp_entries(a, b).unwrap()
+        });
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // index of the largest item. the max of a min-heap is always a leaf so
+    // only heap[n/2..] is scanned; the result is cached in max_hint
+    fn max_slot(&mut self) -> Option<usize> {
+        if let Some(h) = self.max_hint {
+            if self.contains(h) {
+                return Some(self.positions[h]);
+            }
+        }
+        let n = self.heap.len();
+        if n == 0 {
+            return None;
+        }
+        let mut best = n / 2;
+        for i in n / 2 + 1..n {
+            if self.cmp_slots(i, best).unwrap() == Ordering::Greater {
+                best = i;
+            }
+        }
+        self.max_hint = Some(self.heap[best].0);
+        Some(best)
+    }
 
-    #[test]
-    fn test_insert_once() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.insert((0, 42));
-        assert_eq!(mh.heap.len(), 1);
-        assert_eq!(mh.heap[0], (0, 42));
-        assert_eq!(mh.positions.len(), 1);
-        assert_eq!(mh.positions[0], 0);
+    // move the item at index to its place, whichever direction that is
+    fn resift(&mut self, index: usize) {
+        let id = self.heap[index].0;
+        self.bubble_up(index);
+        self.bubble_down(self.positions[id]);
     }
 
-    #[test]
-    fn test_insert_bubble_up() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.insert((5, 69));
-        mh.insert((3, 8));
-        assert_eq!(mh.heap, vec![(3, 8), (5, 69)]);
-        assert_eq!(mh.positions[3], 0);
-        assert_eq!(mh.positions[5], 1);
+    // monotone check and pop log for every popped entry
+    fn note_pop(&mut self, id: usize, key: &K) {
+        if let Some(log) = self.pop_log.as_mut() {
+            log.push((id, key.clone()));
+        }
+        if self.monotone {
+            if let Some(last) = &self.last_popped {
+                assert!(
+                    self.order.cmp_keys(key, last) != Some(Ordering::Less),
+                    "monotone heap: popped a key smaller than the previous pop"
+                );
+            }
+            self.last_popped = Some(key.clone());
+        }
     }
 
-    #[test]
-    fn test_get_min_peek_only() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
+    // start recording every popped entry (delete_min and friends). a
+    // disabled log costs one branch per pop
+    pub fn enable_pop_log(&mut self) {
+        if self.pop_log.is_none() {
+            self.pop_log = Some(Vec::new());
+        }
+    }
+
+    // stop recording and hand back what was logged
+    pub fn disable_pop_log(&mut self) -> Vec<(usize, K)> {
+        self.pop_log.take().unwrap_or_default()
+    }
+
+    // entries popped since enable_pop_log, oldest first
+    pub fn pop_log(&self) -> &[(usize, K)] {
+        self.pop_log.as_deref().unwrap_or(&[])
+    }
+
+    // make a corrupted heap valid again: entries whose positions slot
+    // doesn't point back at them are dropped, then positions is recomputed
+    // from scratch and the array re-heapified. the back-reference check runs
+    // on the array as found, before anything is moved, and tombstones are
+    // dropped in the same pass rather than through compact. an incomparable
+    // key poisons the heap instead of panicking
+    pub fn rebuild(&mut self) {
+        self.poisoned = false;
+        self.max_hint = None;
+        let mut idx = 0;
+        let positions = &self.positions;
+        let dead = &self.dead;
+        self.heap.retain(|(id, _)| {
+            let ok = positions.get(*id) == Some(&idx) && dead.get(*id) != Some(&true);
+            idx += 1;
+            ok
+        });
+        self.dead.fill(false);
+        self.dead_count = 0;
+        self.positions.fill(usize::MAX);
+        for (idx, (id, _)) in self.heap.iter().enumerate() {
+            self.positions[*id] = idx;
+        }
+        if let Err(e) = self.try_sift_all() {
+            self.poison(e);
+        }
+    }
+
+    // panics unless heap order holds and positions is an exact inverse of
+    // the backing array, O(n + ids)
+    pub fn assert_valid(&self) {
+        for (idx, (id, _)) in self.heap.iter().enumerate() {
+            assert_eq!(
+                self.positions.get(*id),
+                Some(&idx),
+                "positions[{id}] doesn't point at heap index {idx}"
+            );
+            if idx > 0 {
+                let parent = (idx - 1) / 2;
+                assert_ne!(
+                    self.order.cmp_entries(&self.heap[parent], &self.heap[idx]),
+                    Some(Ordering::Greater),
+                    "heap order violated between index {parent} and {idx}"
+                );
+            }
+        }
+        let live = self.positions.iter().filter(|&&p| p != usize::MAX).count();
+        assert_eq!(live, self.heap.len(), "positions has stale entries");
+
+        let dead = self.heap.iter().filter(|(id, _)| self.is_dead(*id)).count();
+        assert_eq!(dead, self.dead_count, "tombstone count out of sync");
+        if let Some((id, _)) = self.heap.first() {
+            assert!(!self.is_dead(*id), "tombstone at the root");
+        }
+    }
+
+    // physically drop every tombstone and re-heapify, O(n)
+    fn compact(&mut self) {
+        if self.dead_count == 0 {
+            return;
+        }
+        let dead = &mut self.dead;
+        let positions = &mut self.positions;
+        self.heap.retain(|(id, _)| {
+            if dead.get(*id) == Some(&true) {
+                dead[*id] = false;
+                positions[*id] = usize::MAX;
+                false
+            } else {
+                true
+            }
+        });
+        self.dead_count = 0;
+        self.heapify();
+    }
+
+    // compact that reports incomparable keys instead of panicking: the live
+    // entries are heapified in a scratch heap and only swapped in once that
+    // succeeded, so on error the heap is untouched
+    fn try_compact(&mut self) -> Result<(), HeapError> {
+        if self.dead_count == 0 {
+            return Ok(());
+        }
+        let mut scratch = MinHeap {
+            heap: self.live_entries().cloned().collect(),
+            positions: vec![usize::MAX; self.positions.len()],
+            order: self.order.clone(),
+            ..Self::new()
+        };
+        for (idx, (id, _)) in scratch.heap.iter().enumerate() {
+            scratch.positions[*id] = idx;
+        }
+        scratch.try_sift_all()?;
+
+        for (id, _) in &self.heap {
+            if self.dead.get(*id) == Some(&true) {
+                self.dead[*id] = false;
+            }
+        }
+        self.heap = scratch.heap;
+        self.positions = scratch.positions;
+        self.dead_count = 0;
+        self.max_hint = None;
+        Ok(())
+    }
+
+    // pop tombstones off the root until a live entry is on top
+    fn purge_top(&mut self) {
+        while let Some(&(id, _)) = self.heap.first() {
+            if !self.is_dead(id) {
+                break;
+            }
+            self.dead[id] = false;
+            self.dead_count -= 1;
+            self.remove_at(0);
+        }
+    }
+
+    // recompute positions for every entry and restore heap order with a
+    // bottom-up build, O(n). the fix-up after bulk edits through key_mut;
+    // a lazy heap's tombstones are dropped first so none ends up on top
+    pub fn heapify(&mut self) {
+        if self.dead_count > 0 {
+            // compacting heapifies too
+            self.compact();
+            return;
+        }
+        self.max_hint = None;
+        for (idx, (id, _)) in self.heap.iter().enumerate() {
+            self.positions[*id] = idx;
+        }
+        self.try_sift_all().unwrap()
+    }
+
+    // whether heap is in non-decreasing order, counting comparisons
+    fn is_sorted(&self) -> bool {
+        self.heap.windows(2).all(|w| {
+            self.comparisons.bump();
+            matches!(
+                self.order.cmp_entries(&w[0], &w[1]),
+                Some(Ordering::Less | Ordering::Equal)
+            )
+        })
+    }
+
+    // bottom-up heapify, positions must already be correct
+    fn try_sift_all(&mut self) -> Result<(), HeapError> {
+        let n = self.heap.len();
+        if n > 1 {
+            for i in (0..=(n / 2 - 1)).rev() {
+                self.try_bubble_down(i, n)?;
+            }
+        }
+        Ok(())
+    }
+
+    // remember an incomparable comparison for try_get_min
+    fn poison(&mut self, e: HeapError) -> HeapError {
+        if e == HeapError::Incomparable {
+            self.poisoned = true;
+        }
+        e
+    }
+
+    // replay the comparisons bubble_up would make for entry sitting at
+    // index, without moving anything
+    fn check_sift_up(&self, entry: &(usize, K), mut index: usize) -> Result<(), HeapError> {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            match self.order.cmp_entries(entry, &self.heap[parent]) {
+                Some(Ordering::Less) => index = parent,
+                Some(_) => break,
+                None => return Err(HeapError::Incomparable),
+            }
+        }
+        Ok(())
+    }
+
+    // same for try_bubble_down within heap[..heap_len]. the children along
+    // the path are never moved before they're compared, so reading them in
+    // place gives the same answers
+    fn check_sift_down(
+        &self,
+        entry: &(usize, K),
+        mut index: usize,
+        heap_len: usize,
+    ) -> Result<(), HeapError> {
+        let cmp = |a: &(usize, K), b: &(usize, K)| {
+            self.order.cmp_entries(a, b).ok_or(HeapError::Incomparable)
+        };
+        loop {
+            let left_child = (2 * index) + 1;
+            let right_child = (2 * index) + 2;
+            if left_child >= heap_len {
+                break;
+            }
+            let smaller_child = if right_child < heap_len
+                && cmp(&self.heap[right_child], &self.heap[left_child])? == Ordering::Less
+            {
+                right_child
+            } else {
+                left_child
+            };
+            if cmp(&self.heap[smaller_child], entry)? == Ordering::Less {
+                index = smaller_child;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // compare the keys stored at two heap indices
+    fn cmp_slots(&self, a: usize, b: usize) -> Result<Ordering, HeapError> {
+        self.comparisons.bump();
+        self.order
+            .cmp_entries(&self.heap[a], &self.heap[b])
+            .ok_or(HeapError::Incomparable)
+    }
+
+    // set id's key and sift in whichever direction the active ordering
+    // asks for, so it behaves the same for min, max and custom orderings.
+    // no-op for ids that aren't in the heap, including ids below
+    // positions.len() that were never inserted
+    pub fn change_key(&mut self, id: usize, new_key: K) {
+        let pos_id = match self.slot_of(id) {
+            Some(p) => p,
+            None => return,
+        };
+        self.max_hint = None;
+        self.comparisons.bump();
+        let towards_top =
+            self.order.cmp_keys(&new_key, &self.heap[pos_id].1) != Some(Ordering::Greater);
+        self.heap[pos_id].1 = new_key;
+        if towards_top {
+            if let Some(obs) = self.observer.as_mut() {
+                obs.on_decrease_key(id, &self.heap[pos_id].1);
+            }
+            self.bubble_up(pos_id);
+        } else {
+            self.bubble_down(pos_id);
+        }
+    }
+
+    // change_key for a key moving towards the top: smaller in a min-heap,
+    // larger in a max-heap
+    pub fn decrease_key(&mut self, id: usize, new_key: K) {
+        if let Some(p) = self.slot_of(id) {
+            debug_assert!(
+                self.order.cmp_keys(&new_key, &self.heap[p].1) != Some(Ordering::Greater),
+                "decrease_key: new key orders after the current one"
+            );
+        }
+        self.change_key(id, new_key);
+    }
+
+    // batch of decreases followed by a single O(n) heapify instead of one
+    // sift per update, worth it once updates are a sizeable fraction of len.
+    // every update is checked before anything is written
+    pub fn apply_decreases(&mut self, updates: &[(usize, K)]) -> Result<(), HeapError> {
+        for (id, new_key) in updates {
+            let slot = self.slot_of(*id).ok_or(HeapError::NotPresent(*id))?;
+            self.comparisons.bump();
+            match self.order.cmp_keys(new_key, &self.heap[slot].1) {
+                Some(Ordering::Less) => {}
+                Some(_) => return Err(HeapError::KeyNotSmaller(*id)),
+                None => return Err(self.poison(HeapError::Incomparable)),
+            }
+        }
+
+        for (id, new_key) in updates {
+            let slot = self.positions[*id];
+            self.heap[slot].1 = new_key.clone();
+            if let Some(obs) = self.observer.as_mut() {
+                obs.on_decrease_key(*id, &self.heap[slot].1);
+            }
+        }
+        self.compact();
+        self.heapify();
+        Ok(())
+    }
+
+    // the label-correcting inner loop in one call: apply every improving
+    // update (absent ids, non-improving and incomparable keys are skipped),
+    // then pop the min. a large batch is folded into one O(n) heapify,
+    // a small one gets a sift per update
+    pub fn relax_and_extract(&mut self, updates: &[(usize, K)]) -> Option<(usize, K)> {
+        let mut changed = Vec::new();
+        for (id, new_key) in updates {
+            let Some(slot) = self.slot_of(*id) else {
+                continue;
+            };
+            self.comparisons.bump();
+            if self.order.cmp_keys(new_key, &self.heap[slot].1) == Some(Ordering::Less) {
+                self.heap[slot].1 = new_key.clone();
+                if let Some(obs) = self.observer.as_mut() {
+                    obs.on_decrease_key(*id, &self.heap[slot].1);
+                }
+                changed.push(*id);
+            }
+        }
+
+        let n = self.heap.len().max(2);
+        if changed.len() * n.ilog2() as usize > n {
+            self.compact();
+            self.heapify();
+        } else {
+            if !changed.is_empty() {
+                self.max_hint = None;
+            }
+            for id in changed {
+                self.bubble_up(self.positions[id]);
+            }
+        }
+        self.delete_min()
+    }
+
+    // decrease_key that fails instead of panicking or misbehaving: the id
+    // must be present, the key must get smaller and every comparison the
+    // sift needs must succeed. nothing is modified on error
+    pub fn try_decrease_key(&mut self, id: usize, new_key: K) -> Result<(), HeapError> {
+        let slot = self.slot_of(id).ok_or(HeapError::NotPresent(id))?;
+        let probe = (id, new_key);
+        match self.order.cmp_keys(&probe.1, &self.heap[slot].1) {
+            Some(Ordering::Less) => {}
+            Some(_) => return Err(HeapError::KeyNotSmaller(id)),
+            None => return Err(self.poison(HeapError::Incomparable)),
+        }
+        let checked = self.check_sift_up(&probe, slot);
+        checked.map_err(|e| self.poison(e))?;
+        self.decrease_key(id, probe.1);
+        Ok(())
+    }
+
+    // decrease_key that hands back the key it replaced, None if id is absent
+    pub fn decrease_key_returning(&mut self, id: usize, new_key: K) -> Option<K> {
+        let old_key = self.heap[self.slot_of(id)?].1.clone();
+        self.decrease_key(id, new_key);
+        Some(old_key)
+    }
+
+    // change_key for a key moving away from the top
+    pub fn increase_key(&mut self, id: usize, new_key: K) {
+        if let Some(p) = self.slot_of(id) {
+            debug_assert!(
+                self.order.cmp_keys(&new_key, &self.heap[p].1) != Some(Ordering::Less),
+                "increase_key: new key orders before the current one"
+            );
+        }
+        self.change_key(id, new_key);
+    }
+
+    // current index of id in the backing array. power-user api: the slot
+    // changes on any mutation, so don't hold on to it
+    pub fn slot_of(&self, id: usize) -> Option<usize> {
+        match self.positions.get(id) {
+            Some(&p) if p != usize::MAX && !self.is_dead(id) => Some(p),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.slot_of(id).is_some()
+    }
+
+    // (entries only in self, entries only in other), O(n + m). an id held by
+    // both heaps with different keys shows up on both sides
+    #[allow(clippy::type_complexity)]
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a MinHeap<K>,
+    ) -> (Vec<(usize, K)>, Vec<(usize, K)>) {
+        let only_in = |a: &MinHeap<K>, b: &MinHeap<K>| {
+            a.live_entries()
+                .filter(|(id, key)| match b.slot_of(*id) {
+                    Some(p) => b.heap[p].1 != *key,
+                    None => true,
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        (only_in(self, other), only_in(other, self))
+    }
+
+    // remove an arbitrary item by id, None if it is not in the heap. in lazy
+    // mode the entry is only tombstoned
+    pub fn delete(&mut self, id: usize) -> Option<(usize, K)> {
+        let pos = self.slot_of(id)?;
+        if !self.lazy {
+            let removed = self.remove_at(pos);
+            if let Some(obs) = self.observer.as_mut() {
+                obs.on_delete(id, &removed.1);
+            }
+            return Some(removed);
+        }
+
+        if id >= self.dead.len() {
+            self.dead.resize(id + 1, false);
+        }
+        self.dead[id] = true;
+        self.dead_count += 1;
+        self.ids.release(id);
+        if self.max_hint == Some(id) {
+            self.max_hint = None;
+        }
+        let removed = self.heap[pos].clone();
+        if let Some(obs) = self.observer.as_mut() {
+            obs.on_delete(id, &removed.1);
+        }
+        if self.dead_count * 2 > self.heap.len() {
+            self.compact();
+        } else {
+            self.purge_top();
+        }
+        Some(removed)
+    }
+
+    // take the entry at pos out of the array and restore heap order
+    fn remove_at(&mut self, pos: usize) -> (usize, K) {
+        // same as delete_min: swap with the last item and pop
+        let last_item = self.heap.len() - 1;
+        self.heap.swap(pos, last_item);
+        let removed = self.heap.pop().unwrap();
+        self.positions[removed.0] = usize::MAX;
+        self.ids.release(removed.0);
+        if self.max_hint == Some(removed.0) {
+            self.max_hint = None;
+        }
+
+        // the moved item may have to go either way
+        if pos < self.heap.len() {
+            let moved_id = self.heap[pos].0;
+            self.positions[moved_id] = pos;
+            self.resift(pos);
+        }
+
+        removed
+    }
+
+    // exchange the keys of two ids and restore heap order
+    pub fn swap_keys(&mut self, a: usize, b: usize) -> Result<(), HeapError> {
+        let pa = self.slot_of(a).ok_or(HeapError::NotPresent(a))?;
+        let pb = self.slot_of(b).ok_or(HeapError::NotPresent(b))?;
+        if a == b {
+            return Ok(());
+        }
+
+        // swap whole entries, then put the ids back where they were
+        self.heap.swap(pa, pb);
+        self.heap[pa].0 = a;
+        self.heap[pb].0 = b;
+        self.max_hint = None;
+
+        self.resift(pa);
+        self.resift(self.positions[b]);
+        Ok(())
+    }
+}
+
+// one place to configure ordering and modes of a MinHeap
+pub struct MinHeapBuilder<K> {
+    capacity: usize,
+    max_order: bool,
+    stable: bool,
+    monotone: bool,
+    comparator: Option<Comparator<K>>,
+}
+
+impl<K: PartialOrd + Clone> Default for MinHeapBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone> MinHeapBuilder<K> {
+    pub fn new() -> Self {
+        MinHeapBuilder {
+            capacity: 0,
+            max_order: false,
+            stable: false,
+            monotone: false,
+            comparator: None,
+        }
+    }
+
+    // preallocate room for this many items (and ids below it)
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    // pop the largest key first
+    pub fn max_order(mut self, yes: bool) -> Self {
+        self.max_order = yes;
+        self
+    }
+
+    // break key ties by the smaller id, so equal keys pop deterministically
+    pub fn stable(mut self, yes: bool) -> Self {
+        self.stable = yes;
+        self
+    }
+
+    // panic when a pop comes out of order, see MinHeap::new_monotone
+    pub fn monotone(mut self, yes: bool) -> Self {
+        self.monotone = yes;
+        self
+    }
+
+    // order keys with cmp instead of PartialOrd; Less pops first
+    pub fn comparator<F>(mut self, cmp: F) -> Self
+    where
+        F: Fn(&K, &K) -> Ordering + Send + Sync + 'static,
+    {
+        self.comparator = Some(Arc::new(cmp));
+        self
+    }
+
+    pub fn build(self) -> Result<MinHeap<K>, HeapError> {
+        let kind = match (self.comparator, self.max_order) {
+            (Some(_), true) => {
+                return Err(HeapError::InvalidConfig(
+                    "a custom comparator can't be combined with max_order",
+                ))
+            }
+            (Some(cmp), false) => OrderKind::Custom(cmp),
+            (None, true) => OrderKind::Reversed,
+            (None, false) => OrderKind::Natural,
+        };
+        Ok(MinHeap {
+            heap: Vec::with_capacity(self.capacity),
+            positions: Vec::with_capacity(self.capacity),
+            order: KeyOrder {
+                kind,
+                stable: self.stable,
+            },
+            monotone: self.monotone,
+            ..MinHeap::new()
+        })
+    }
+}
+
+// returned by MinHeap::insert_handle: an id plus the slot its item sits at.
+// the slot is shared with the heap, which rewrites it on every sift that
+// moves the item, so decrease_key_handle can go straight to the entry
+#[derive(Debug, Clone)]
+pub struct SlotHandle {
+    id: usize,
+    slot: Arc<AtomicUsize>,
+}
+
+impl SlotHandle {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+// iterator returned by MinHeap::drain_sorted
+pub struct DrainSorted<'a, K: PartialOrd + Clone> {
+    heap: &'a mut MinHeap<K>,
+}
+
+impl<K: PartialOrd + Clone> Iterator for DrainSorted<'_, K> {
+    type Item = (usize, K);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.heap.delete_min()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<K: PartialOrd + Clone> Drop for DrainSorted<'_, K> {
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+// iterator returned by MinHeap::iter_sorted
+pub struct SortedIter<'a, K> {
+    heap: &'a MinHeap<K>,
+    // slots of the real array, kept as a binary heap by their entries
+    frontier: Vec<usize>,
+}
+
+impl<K: PartialOrd + Clone> SortedIter<'_, K> {
+    fn before(&self, a: usize, b: usize) -> bool {
+        self.heap.cmp_slots(self.frontier[a], self.frontier[b]) == Ok(Ordering::Less)
+    }
+
+    fn push(&mut self, slot: usize) {
+        self.frontier.push(slot);
+        let mut i = self.frontier.len() - 1;
+        while i > 0 && self.before(i, (i - 1) / 2) {
+            self.frontier.swap(i, (i - 1) / 2);
+            i = (i - 1) / 2;
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let top = self.frontier.pop()?;
+        if self.frontier.is_empty() {
+            return Some(top);
+        }
+        let top = std::mem::replace(&mut self.frontier[0], top);
+        let n = self.frontier.len();
+        let mut i = 0;
+        loop {
+            let mut m = i;
+            for c in [2 * i + 1, 2 * i + 2] {
+                if c < n && self.before(c, m) {
+                    m = c;
+                }
+            }
+            if m == i {
+                break;
+            }
+            self.frontier.swap(i, m);
+            i = m;
+        }
+        Some(top)
+    }
+}
+
+impl<K: PartialOrd + Clone> Iterator for SortedIter<'_, K> {
+    type Item = (usize, K);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let slot = self.pop()?;
+            for c in [2 * slot + 1, 2 * slot + 2] {
+                if c < self.heap.heap.len() {
+                    self.push(c);
+                }
+            }
+            // tombstones still order their subtrees, just skip them
+            let entry = &self.heap.heap[slot];
+            if !self.heap.is_dead(entry.0) {
+                return Some(entry.clone());
+            }
+        }
+    }
+}
+
+// iterator returned by MinHeap::into_draining_iter
+pub struct DrainingIter<K> {
+    heap: MinHeap<K>,
+}
+
+impl<K: PartialOrd + Clone> Iterator for DrainingIter<K> {
+    type Item = (usize, K);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.heap.delete_min()?;
+        let len = self.heap.heap.len();
+        if len.is_power_of_two() && self.heap.heap.capacity() > len {
+            self.heap.heap.shrink_to_fit();
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<K: PartialOrd + Clone> ExactSizeIterator for DrainingIter<K> {}
+
+// iterator adaptor returned by MinHeap::watch
+pub struct MinWatcher<'a, K, I> {
+    heap: &'a mut MinHeap<K>,
+    items: I,
+}
+
+impl<K: PartialOrd + Clone, I: Iterator<Item = (usize, K)>> Iterator for MinWatcher<'_, K, I> {
+    type Item = ((usize, K), (usize, K));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.items.next()?;
+        let min = self.heap.insert_observing(item.clone())?;
+        Some((item, min))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+// tombstone helpers, usable without any key bounds
+impl<K> MinHeap<K> {
+    fn is_dead(&self, id: usize) -> bool {
+        self.dead_count > 0 && self.dead.get(id) == Some(&true)
+    }
+
+    fn live_entries(&self) -> impl Iterator<Item = &(usize, K)> {
+        self.heap.iter().filter(|(id, _)| !self.is_dead(*id))
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<K> MinHeap<K> {
+    // number of key comparisons since creation or the last reset
+    pub fn comparison_count(&self) -> u64 {
+        self.comparisons.get()
+    }
+
+    pub fn reset_comparison_count(&mut self) {
+        self.comparisons.reset();
+    }
+
+    // all operation counters at once, only comparisons and sift_steps
+    // apply to a binary heap
+    pub fn op_counters(&self) -> OpCounters {
+        OpCounters {
+            comparisons: self.comparisons.get(),
+            sift_steps: self.sift_steps.get(),
+            ..OpCounters::default()
+        }
+    }
+}
+
+impl<K: PartialOrd + Clone + Sub<Output = K>> MinHeap<K> {
+    // decrease_key to key - delta. like decrease_key the result has to move
+    // towards the top under the active ordering, so delta is positive in a
+    // min-heap and negative in a max-heap
+    pub fn decrease_key_by(&mut self, id: usize, delta: K) {
+        let old_key = match self.slot_of(id) {
+            Some(p) => &self.heap[p].1,
+            None => return,
+        };
+        let new_key = old_key.clone() - delta;
+        debug_assert!(
+            self.order.cmp_keys(&new_key, old_key) == Some(Ordering::Less),
+            "decrease_key_by: key - delta doesn't move towards the top"
+        );
+        self.decrease_key(id, new_key);
+    }
+}
+
+// move id out of `from` and into `to` under new_key, e.g. a decrease that
+// crosses buckets. returns the key it had in `from`. new_key is not compared
+// with the old one, a caller that only allows decreases checks that first.
+// fails with NotPresent if `from` lacks id, DuplicateId if `to` already
+// holds it or CapacityExceeded if `to` is at its max_len, and in all those
+// cases neither heap is touched
+pub fn transfer<K: PartialOrd + Clone>(
+    from: &mut MinHeap<K>,
+    to: &mut MinHeap<K>,
+    id: usize,
+    new_key: K,
+) -> Result<K, HeapError> {
+    if !from.contains(id) {
+        return Err(HeapError::NotPresent(id));
+    }
+    if to.contains(id) {
+        return Err(HeapError::DuplicateId(id));
+    }
+    if to.max_len.is_some_and(|m| to.len() >= m) {
+        return Err(HeapError::CapacityExceeded);
+    }
+    let (_, old_key) = from.delete(id).unwrap();
+    to.insert((id, new_key));
+    Ok(old_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_once() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.insert((0, 42));
+        assert_eq!(mh.heap.len(), 1);
+        assert_eq!(mh.heap[0], (0, 42));
+        assert_eq!(mh.positions.len(), 1);
+        assert_eq!(mh.positions[0], 0);
+    }
+
+    #[test]
+    fn test_insert_bubble_up() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.insert((5, 69));
+        mh.insert((3, 8));
+        assert_eq!(mh.heap, vec![(3, 8), (5, 69)]);
+        assert_eq!(mh.positions[3], 0);
+        assert_eq!(mh.positions[5], 1);
+    }
+
+    #[test]
+    fn test_get_min_peek_only() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
         mh.insert((0, 20));
         mh.insert((1, 10));
-        let first = *mh.get_min().unwrap();
-        let second = *mh.get_min().unwrap();
-        assert_eq!(first, (1, 10));
-        assert_eq!(second, (1, 10));
-        assert_eq!(mh.heap.len(), 2);
+        let first = *mh.get_min().unwrap();
+        let second = *mh.get_min().unwrap();
+        assert_eq!(first, (1, 10));
+        assert_eq!(second, (1, 10));
+        assert_eq!(mh.heap.len(), 2);
+    }
+
+    #[test]
+    fn test_peek_matches_get_min() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        assert_eq!(mh.peek(), None);
+        mh.insert((0, 20));
+        mh.insert((1, 10));
+        assert_eq!(mh.peek(), mh.get_min().copied());
+        mh.delete_min();
+        assert_eq!(mh.peek(), Some((0, 20)));
+    }
+
+    #[test]
+    fn test_delete_min_basic() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.insert((0, 20));
+        mh.insert((1, 10));
+        mh.insert((2, 30));
+        let min = mh.delete_min().unwrap();
+        assert_eq!(min, (1, 10));
+        assert_eq!(*mh.get_min().unwrap(), (0, 20));
+        assert_eq!(mh.positions[0], 0);
+        assert_eq!(mh.positions[2], 1);
+    }
+
+    #[test]
+    fn test_delete_min_empty() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        assert!(mh.delete_min().is_none());
+    }
+
+    #[test]
+    fn test_bubble_up_manual() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.heap = vec![(0, 10), (1, 5)];
+        mh.positions = vec![0, 1];
+        mh.bubble_up(1);
+        assert_eq!(mh.heap, vec![(1, 5), (0, 10)]);
+        assert_eq!(mh.positions, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_bubble_down_manual() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.heap = vec![(0, 50), (1, 20), (2, 30)];
+        mh.positions = vec![0, 1, 2];
+        mh.bubble_down(0);
+        assert_eq!(mh.heap[0], (1, 20));
+        assert_eq!(mh.positions[1], 0);
+        assert_eq!(mh.positions[0], 1);
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.insert((3, 15));
+        mh.insert((2, 25));
+        mh.insert((5, 5));
+        assert_eq!(*mh.get_min().unwrap(), (5, 5));
+        let order: Vec<_> = (0..3).map(|_| mh.delete_min().unwrap()).collect();
+        assert_eq!(order, vec![(5, 5), (3, 15), (2, 25)]);
+        assert!(mh.delete_min().is_none());
+    }
+
+    #[test]
+    fn test_build_heap() {
+        let items = vec![(2, 50), (0, 10), (3, 20), (1, 5)];
+        let mut mh = MinHeap::build_heap(items);
+        assert_eq!(*mh.get_min().unwrap(), (1, 5));
+        assert_eq!(mh.positions[1], 0);
+        assert_eq!(mh.heap.len(), 4);
+        let order: Vec<_> = (0..4).map(|_| mh.delete_min().unwrap()).collect();
+        assert_eq!(order, vec![(1, 5), (0, 10), (3, 20), (2, 50)]);
+        assert!(mh.delete_min().is_none());
+    }
+
+    #[test]
+    fn test_try_build_heap_duplicate_id() {
+        let items = vec![(0, 10), (1, 5), (0, 7)];
+        assert_eq!(
+            MinHeap::try_build_heap(items).err(),
+            Some(HeapError::DuplicateId(0))
+        );
+    }
+
+    #[test]
+    fn test_try_build_heap_nan_key() {
+        let items = vec![(0, 1.0), (1, f64::NAN), (2, 0.5)];
+        assert_eq!(
+            MinHeap::try_build_heap(items).err(),
+            Some(HeapError::Incomparable)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_build_heap_panics_on_duplicate_id() {
+        MinHeap::build_heap(vec![(3, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_clone_from_reuses_capacity() {
+        let template = MinHeap::build_heap(vec![(2, 50), (0, 10), (3, 20), (1, 5)]);
+        let mut work: MinHeap<i32> = MinHeap::new();
+
+        work.clone_from(&template);
+        while work.delete_min().is_some() {}
+        let heap_cap = work.heap.capacity();
+        let pos_cap = work.positions.capacity();
+
+        work.clone_from(&template);
+        assert!(work.heap.capacity() >= heap_cap);
+        assert!(work.positions.capacity() >= pos_cap);
+        assert_eq!(work.heap, template.heap);
+        assert_eq!(work.positions, template.positions);
+        assert_eq!(work.delete_min(), Some((1, 5)));
+    }
+
+    #[test]
+    fn test_monotone_allows_non_decreasing_pops() {
+        let mut mh: MinHeap<i32> = MinHeap::new_monotone();
+        mh.insert((0, 10));
+        mh.insert((1, 10));
+        mh.insert((2, 20));
+        assert_eq!(mh.delete_min().unwrap().1, 10);
+        mh.insert((3, 15));
+        assert_eq!(mh.delete_min().unwrap().1, 10);
+        assert_eq!(mh.delete_min(), Some((3, 15)));
+        assert_eq!(mh.delete_min(), Some((2, 20)));
+    }
+
+    #[test]
+    #[should_panic(expected = "monotone heap")]
+    fn test_monotone_panics_on_smaller_pop() {
+        let mut mh: MinHeap<i32> = MinHeap::new_monotone();
+        mh.insert((0, 10));
+        mh.insert((1, 20));
+        mh.delete_min();
+        // simulates relaxing a negative edge
+        mh.insert((2, 5));
+        mh.delete_min();
+    }
+
+    #[test]
+    fn test_clear_keeps_capacity_and_reuses_ids() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        for id in 0..64 {
+            mh.insert((id, 100 - id as i32));
+        }
+        let heap_cap = mh.heap.capacity();
+        let pos_cap = mh.positions.capacity();
+
+        mh.clear();
+        assert!(mh.is_empty());
+        assert!(mh.positions.iter().all(|&p| p == usize::MAX));
+        assert_eq!(mh.heap.capacity(), heap_cap);
+        assert_eq!(mh.positions.capacity(), pos_cap);
+
+        mh.insert((5, 3));
+        mh.insert((63, 1));
+        mh.insert((0, 2));
+        assert_eq!(mh.delete_min(), Some((63, 1)));
+        assert_eq!(mh.delete_min(), Some((0, 2)));
+        assert_eq!(mh.delete_min(), Some((5, 3)));
+        assert!(mh.delete_min().is_none());
+
+        // tombstones are cleared along with the live entries
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..10 {
+            mh.insert((id, id as i32));
+        }
+        mh.delete(4);
+        mh.delete(8);
+        mh.clear();
+        assert!(mh.dead.iter().all(|&d| !d));
+        mh.insert((4, 1));
+        mh.insert((8, 0));
+        assert_eq!(mh.len(), 2);
+        assert_eq!(mh.delete_min(), Some((8, 0)));
+        assert_eq!(mh.delete_min(), Some((4, 1)));
+    }
+
+    #[test]
+    fn test_split_off_threshold() {
+        let items: Vec<_> = (0..20).map(|id| (id, (id * 7 % 20) as i32)).collect();
+        let mut low = MinHeap::build_heap(items);
+        let mut high = low.split_off(|&k| k >= 10);
+
+        low.assert_valid();
+        high.assert_valid();
+        assert_eq!(low.len(), 10);
+        assert_eq!(high.len(), 10);
+
+        let mut low_keys = Vec::new();
+        while let Some((id, key)) = low.delete_min() {
+            assert_eq!(key, (id * 7 % 20) as i32);
+            low_keys.push(key);
+        }
+        let mut high_keys = Vec::new();
+        while let Some((id, key)) = high.delete_min() {
+            assert_eq!(key, (id * 7 % 20) as i32);
+            high_keys.push(key);
+        }
+        assert_eq!(low_keys, (0..10).collect::<Vec<_>>());
+        assert_eq!(high_keys, (10..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_pop_k() {
+        let items: Vec<_> = (0..10).map(|id| (id, (id * 3 % 10) as i32)).collect();
+
+        let mut mh = MinHeap::build_heap(items.clone());
+        assert!(mh.pop_k(0).is_empty());
+        assert_eq!(mh.len(), 10);
+
+        // small k takes the repeated delete_min path
+        let mut mh = MinHeap::build_heap(items.clone());
+        let popped = mh.pop_k(3);
+        assert_eq!(popped, vec![(0, 0), (7, 1), (4, 2)]);
+        mh.assert_valid();
+        assert_eq!(mh.len(), 7);
+        assert_eq!(mh.peek(), Some((1, 3)));
+
+        // mid-range k takes the sort path
+        let mut mh = MinHeap::build_heap(items.clone());
+        let popped = mh.pop_k(6);
+        let keys: Vec<_> = popped.iter().map(|&(_, k)| k).collect();
+        assert_eq!(keys, vec![0, 1, 2, 3, 4, 5]);
+        mh.assert_valid();
+        let rest: Vec<_> = (0..4).map(|_| mh.delete_min().unwrap().1).collect();
+        assert_eq!(rest, vec![6, 7, 8, 9]);
+
+        let mut mh = MinHeap::build_heap(items);
+        let popped = mh.pop_k(10);
+        assert_eq!(popped.len(), 10);
+        assert!(popped.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(mh.is_empty());
+        assert!(mh.positions.iter().all(|&p| p == usize::MAX));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_comparison_count() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        // ascending inserts never move: one comparison each after the first
+        for id in 0..8 {
+            mh.insert((id, id as i32));
+        }
+        assert_eq!(mh.comparison_count(), 7);
+
+        mh.reset_comparison_count();
+        assert_eq!(mh.comparison_count(), 0);
+
+        // every pop sifts down through at most log2(n) levels,
+        // two comparisons per level
+        while mh.delete_min().is_some() {}
+        let count = mh.comparison_count();
+        assert!(count > 0 && count <= 8 * 2 * 3, "count = {count}");
+    }
+
+    #[test]
+    fn test_insert_observing() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        assert_eq!(mh.insert_observing((0, 10)), Some((0, 10)));
+        assert_eq!(mh.insert_observing((1, 20)), Some((0, 10)));
+        assert_eq!(mh.insert_observing((2, 5)), Some((2, 5)));
+    }
+
+    #[test]
+    fn test_watch_descending_stream() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        let stream = (0..10).map(|id| (id, 100 - id as i32));
+        for (inserted, min) in mh.watch(stream) {
+            // every element of a descending stream becomes the new min
+            assert_eq!(inserted, min);
+        }
+        assert_eq!(mh.len(), 10);
+        assert_eq!(mh.peek(), Some((9, 91)));
+    }
+
+    #[test]
+    fn test_to_vec_snapshot() {
+        let mut mh = MinHeap::build_heap(vec![(2, 50), (0, 10), (3, 20), (1, 5)]);
+        mh.delete_min();
+        let snapshot = mh.to_vec();
+        assert_eq!(snapshot.len(), mh.len());
+        assert_eq!(snapshot.capacity(), mh.len());
+        assert_eq!(snapshot, mh.heap);
+
+        let mut sorted = snapshot;
+        sorted.sort_by_key(|e| e.1);
+        assert_eq!(sorted, mh.clone().into_sorted_vec());
+        assert_eq!(sorted, vec![(0, 10), (3, 20), (2, 50)]);
+    }
+
+    #[test]
+    fn test_delete_by_id() {
+        let items: Vec<_> = (0..10).map(|id| (id, (id * 7 % 10) as i32)).collect();
+        let mut mh = MinHeap::build_heap(items);
+        assert_eq!(mh.delete(3), Some((3, 1)));
+        assert_eq!(mh.delete(3), None);
+        assert_eq!(mh.delete(42), None);
+        assert!(!mh.contains(3));
+        assert!(mh.contains(4));
+        mh.assert_valid();
+
+        let keys: Vec<_> = (0..9).map(|_| mh.delete_min().unwrap().1).collect();
+        assert_eq!(keys, vec![0, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_slot_of() {
+        let mut mh = MinHeap::build_heap(vec![(2, 50), (0, 10), (3, 20), (1, 5)]);
+        for id in 0..4 {
+            assert_eq!(mh.slot_of(id), Some(mh.positions[id]));
+            assert_eq!(mh.heap[mh.slot_of(id).unwrap()].0, id);
+        }
+        mh.delete_min();
+        mh.delete(3);
+        assert_eq!(mh.slot_of(1), None);
+        assert_eq!(mh.slot_of(3), None);
+        assert_eq!(mh.slot_of(99), None);
+        assert_eq!(mh.slot_of(0), Some(0));
+    }
+
+    #[test]
+    fn test_into_sorted_vec_desc() {
+        let items: Vec<_> = (0..50).map(|id| (id, (id * 31 % 50) as i32)).collect();
+        let mh = MinHeap::build_heap(items);
+        let mut asc = mh.clone().into_sorted_vec();
+        asc.reverse();
+        assert_eq!(mh.into_sorted_vec_desc(), asc);
+
+        let empty: MinHeap<i32> = MinHeap::new();
+        assert!(empty.into_sorted_vec_desc().is_empty());
+    }
+
+    #[test]
+    fn test_swap_keys() {
+        let items: Vec<_> = (0..7).map(|id| (id, id as i32 * 10)).collect();
+        let mut mh = MinHeap::build_heap(items);
+        // id 0 is the root, id 6 a leaf
+        mh.swap_keys(0, 6).unwrap();
+        mh.assert_valid();
+        assert_eq!(mh.peek(), Some((6, 0)));
+
+        assert_eq!(mh.swap_keys(2, 2), Ok(()));
+        assert_eq!(mh.swap_keys(2, 42), Err(HeapError::NotPresent(42)));
+        assert_eq!(mh.swap_keys(42, 2), Err(HeapError::NotPresent(42)));
+
+        let order: Vec<_> = (0..7).map(|_| mh.delete_min().unwrap()).collect();
+        assert_eq!(
+            order,
+            vec![(6, 0), (1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (0, 60)]
+        );
+    }
+
+    #[test]
+    fn test_insert_or_evict_max() {
+        let mut mh: MinHeap<i32> = MinHeap::with_fixed_capacity(3);
+        let keys = [50, 20, 80, 10, 90, 30, 5, 60, 25, 1];
+        for (id, &key) in keys.iter().enumerate() {
+            let evicted = mh.insert_or_evict_max((id, key));
+            assert!(mh.len() <= 3);
+            mh.assert_valid();
+
+            let mut smallest: Vec<i32> = keys[..=id].to_vec();
+            smallest.sort();
+            smallest.truncate(3);
+            let mut kept: Vec<i32> = mh.heap.iter().map(|&(_, k)| k).collect();
+            kept.sort();
+            assert_eq!(kept, smallest);
+
+            if id >= 3 {
+                let (_, k) = evicted.unwrap();
+                assert!(kept.iter().all(|&x| x <= k));
+            } else {
+                assert!(evicted.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_observer_callbacks() {
+        use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        struct Counts {
+            inserts: AtomicUsize,
+            pops: AtomicUsize,
+            decreases: AtomicUsize,
+            deletes: AtomicUsize,
+        }
+        struct Recorder(Arc<Counts>);
+        impl HeapObserver<i32> for Recorder {
+            fn on_insert(&mut self, _id: usize, _key: &i32) {
+                self.0.inserts.fetch_add(1, Relaxed);
+            }
+            fn on_delete_min(&mut self, _id: usize, _key: &i32) {
+                self.0.pops.fetch_add(1, Relaxed);
+            }
+            fn on_decrease_key(&mut self, id: usize, key: &i32) {
+                assert_eq!((id, *key), (2, 1));
+                self.0.decreases.fetch_add(1, Relaxed);
+            }
+            fn on_delete(&mut self, _id: usize, _key: &i32) {
+                self.0.deletes.fetch_add(1, Relaxed);
+            }
+        }
+
+        let counts = Arc::new(Counts::default());
+        let mut mh = MinHeap::with_observer(Recorder(Arc::clone(&counts)));
+        for id in 0..5 {
+            mh.insert((id, 10 * id as i32));
+        }
+        mh.decrease_key(2, 1);
+        mh.delete_min();
+        mh.delete_min();
+        assert_eq!(counts.inserts.load(Relaxed), 5);
+        assert_eq!(counts.pops.load(Relaxed), 2);
+        assert_eq!(counts.decreases.load(Relaxed), 1);
+
+        // clones don't share the observer
+        let mut copy = mh.clone();
+        copy.delete_min();
+        assert_eq!(counts.pops.load(Relaxed), 2);
+
+        // pop_k fires once per item whether it pops one by one or sorts
+        let counts = Arc::new(Counts::default());
+        let mut mh = MinHeap::with_observer(Recorder(Arc::clone(&counts)));
+        for id in 0..10 {
+            mh.insert((id, 10 * id as i32));
+        }
+        mh.pop_k(2);
+        assert_eq!(counts.pops.load(Relaxed), 2);
+        mh.pop_k(6);
+        assert_eq!(counts.pops.load(Relaxed), 8);
+        mh.insert((10, 5));
+        mh.pop_all();
+        assert_eq!(counts.pops.load(Relaxed), 11);
+
+        // other removals report through on_delete
+        for id in 0..4 {
+            mh.insert((id, 10 * id as i32));
+        }
+        mh.delete(1);
+        mh.delete_max();
+        assert_eq!(counts.deletes.load(Relaxed), 2);
+        assert_eq!(counts.pops.load(Relaxed), 11);
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let mut mh: MinHeap<i32> = MinHeap::builder().capacity(16).build().unwrap();
+        assert!(mh.heap.capacity() >= 16 && mh.positions.capacity() >= 16);
+        mh.insert((0, 2));
+        mh.insert((1, 1));
+        assert_eq!(mh.peek(), Some((1, 1)));
+
+        let mut max = MinHeap::builder().max_order(true).build().unwrap();
+        for (id, key) in [(0, 5), (1, 9), (2, 1)] {
+            max.insert((id, key));
+        }
+        max.assert_valid();
+        let keys: Vec<_> = (0..3).map(|_| max.delete_min().unwrap().1).collect();
+        assert_eq!(keys, vec![9, 5, 1]);
+
+        // order by distance from 10
+        let mut by_dist = MinHeap::builder()
+            .comparator(|a: &i32, b: &i32| (a - 10).abs().cmp(&(b - 10).abs()))
+            .build()
+            .unwrap();
+        for (id, key) in [(0, 0), (1, 12), (2, 9), (3, 30)] {
+            by_dist.insert((id, key));
+        }
+        let keys: Vec<_> = by_dist.into_sorted_vec().iter().map(|e| e.1).collect();
+        assert_eq!(keys, vec![9, 12, 0, 30]);
+
+        let mut stable = MinHeap::builder().stable(true).build().unwrap();
+        for id in [4, 1, 3, 0, 2] {
+            stable.insert((id, 7));
+        }
+        let ids: Vec<_> = (0..5).map(|_| stable.delete_min().unwrap().0).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "monotone heap")]
+    fn test_builder_monotone_follows_order() {
+        let mut mh = MinHeap::builder()
+            .max_order(true)
+            .monotone(true)
+            .build()
+            .unwrap();
+        mh.insert((0, 5));
+        mh.delete_min();
+        mh.insert((1, 8)); // larger pops first in max order, so this is out of order
+        mh.delete_min();
+    }
+
+    #[test]
+    fn test_builder_rejects_conflicts() {
+        let res = MinHeap::builder()
+            .comparator(|a: &i32, b: &i32| b.cmp(a))
+            .max_order(true)
+            .build();
+        assert!(matches!(res, Err(HeapError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_rebuild_after_corruption() {
+        let items: Vec<_> = (0..10).map(|id| (id, (id * 3 % 10) as i32)).collect();
+
+        // a stale duplicate of id 4 that positions doesn't point at
+        let mut mh = MinHeap::build_heap(items.clone());
+        mh.heap.push((4, -1));
+        mh.rebuild();
+        mh.assert_valid();
+        assert_eq!(mh.len(), 10);
+        assert_eq!(mh.peek(), Some((0, 0)));
+
+        // positions pointing at the wrong slots: those entries are dropped
+        let mut mh = MinHeap::build_heap(items.clone());
+        mh.positions.swap(2, 7);
+        mh.positions[5] = usize::MAX;
+        mh.rebuild();
+        mh.assert_valid();
+        assert_eq!(mh.len(), 7);
+        assert!(!mh.contains(2) && !mh.contains(5) && !mh.contains(7));
+        let mut ids: Vec<_> = mh.to_vec().iter().map(|e| e.0).collect();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 3, 4, 6, 8, 9]);
+
+        // lazy heap with a tombstone: the corruption must still be seen,
+        // compacting first would have rewritten positions and hidden it
+        let mut mh = MinHeap::new_lazy();
+        for &item in &items {
+            mh.insert(item);
+        }
+        mh.delete(3);
+        mh.positions.swap(2, 7);
+        mh.rebuild();
+        mh.assert_valid();
+        let mut ids: Vec<_> = mh.to_vec().iter().map(|e| e.0).collect();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 4, 5, 6, 8, 9]);
+
+        // an incomparable key poisons the heap rather than panicking
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..10 {
+            mh.insert((id, id as f64));
+        }
+        mh.delete(7);
+        *mh.key_mut(5).unwrap() = f64::NAN;
+        mh.rebuild();
+        assert_eq!(mh.try_get_min(), Err(HeapError::Incomparable));
+    }
+
+    #[test]
+    #[should_panic(expected = "positions")]
+    fn test_assert_valid_detects_corruption() {
+        let mut mh = MinHeap::build_heap(vec![(0, 1), (1, 2), (2, 3)]);
+        mh.positions.swap(1, 2);
+        mh.assert_valid();
+    }
+
+    #[test]
+    fn test_range() {
+        let items: Vec<_> = (0..100).map(|id| (id, (id * 37 % 100) as i32)).collect();
+        let mh = MinHeap::build_heap(items.clone());
+
+        for (lo, hi) in [(0, 100), (10, 20), (42, 43), (50, 50), (90, 200), (-5, 3)] {
+            let mut got: Vec<_> = mh.range(lo, hi).cloned().collect();
+            got.sort();
+            let expected: Vec<_> = items
+                .iter()
+                .filter(|&&(_, k)| lo <= k && k < hi)
+                .cloned()
+                .collect();
+            assert_eq!(got, expected);
+        }
+        assert_eq!(mh.range(60, 40).count(), 0);
+    }
+
+    #[test]
+    fn test_delete_min_group() {
+        let mut mh = MinHeap::new();
+        assert!(mh.delete_min_group().is_empty());
+
+        for (id, k) in [(0, 5), (1, 2), (2, 9), (3, 2), (4, 3), (5, 2), (6, 3)] {
+            mh.insert((id, k));
+        }
+        let mut group = mh.delete_min_group();
+        group.sort();
+        assert_eq!(group, vec![(1, 2), (3, 2), (5, 2)]);
+        mh.assert_valid();
+
+        let mut group = mh.delete_min_group();
+        group.sort();
+        assert_eq!(group, vec![(4, 3), (6, 3)]);
+        assert_eq!(mh.delete_min_group(), vec![(0, 5)]);
+        assert_eq!(mh.delete_min_group(), vec![(2, 9)]);
+        assert!(mh.is_empty());
+    }
+
+    #[test]
+    fn test_from_keys() {
+        let keys = vec![7.5, 1.25, 9.0, 3.5, 0.5, 4.0];
+        let from_keys = MinHeap::from_keys(keys.clone());
+        let explicit = MinHeap::build_heap(keys.into_iter().enumerate().collect());
+        from_keys.assert_valid();
+        assert_eq!(from_keys.to_vec(), explicit.to_vec());
+        assert_eq!(from_keys.positions, explicit.positions);
+        assert_eq!(from_keys.peek(), Some((4, 0.5)));
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let mut mh = MinHeap::from_keys(vec![1, 2, 3, 4, 5, 6, 7]);
+        // distance from 4 isn't monotone in the key
+        mh.map_keys(|&k: &i32| (k - 4).abs());
+        mh.assert_valid();
+        assert_eq!(mh.peek(), Some((3, 0)));
+
+        let keys: Vec<_> = mh.into_sorted_vec().iter().map(|e| e.1).collect();
+        assert_eq!(keys, vec![0, 1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let a = MinHeap::build_heap(vec![(0, 10), (1, 20), (2, 30), (4, 50)]);
+        let b = MinHeap::build_heap(vec![(4, 50), (2, 35), (0, 10), (7, 70), (1, 20)]);
+
+        let (mut only_a, mut only_b) = a.symmetric_difference(&b);
+        only_a.sort();
+        only_b.sort();
+        assert_eq!(only_a, vec![(2, 30)]);
+        assert_eq!(only_b, vec![(2, 35), (7, 70)]);
+
+        let (x, y) = a.symmetric_difference(&a.clone());
+        assert!(x.is_empty() && y.is_empty());
+    }
+
+    #[test]
+    fn test_eq_ignores_layout() {
+        let items = vec![(0, 4), (1, 1), (2, 3), (3, 1), (4, 2)];
+        let a = MinHeap::build_heap(items.clone());
+        let mut b = MinHeap::new();
+        for item in items.into_iter().rev() {
+            b.insert(item);
+        }
+        assert_ne!(a.to_vec(), b.to_vec());
+        assert!(a == b);
+
+        b.decrease_key(0, 0);
+        assert!(a != b);
+        b.delete(0);
+        assert!(a != b);
+    }
+
+    #[test]
+    fn test_absent_id_below_positions_len() {
+        let mut mh = MinHeap::new();
+        mh.insert((100, 7));
+        mh.insert((3, 9));
+
+        // id 50 has a positions slot (because of id 100) but was never inserted
+        mh.decrease_key(50, 1);
+        assert_eq!(mh.delete(50), None);
+        mh.assert_valid();
+        assert_eq!(mh.len(), 2);
+        assert_eq!(mh.peek(), Some((100, 7)));
+
+        // same for an id that was inserted and popped again
+        mh.delete_min();
+        mh.decrease_key(100, 0);
+        assert_eq!(mh.delete(100), None);
+        assert_eq!(mh.peek(), Some((3, 9)));
+    }
+
+    #[test]
+    fn test_change_key_min_and_max() {
+        let items: Vec<_> = (0..8).map(|id| (id, id as i32 * 10)).collect();
+
+        let mut min = MinHeap::build_heap(items.clone());
+        min.change_key(0, 75);
+        min.change_key(6, -1);
+        min.assert_valid();
+        assert_eq!(min.peek(), Some((6, -1)));
+        min.increase_key(6, 100);
+        min.decrease_key(3, 5);
+        min.assert_valid();
+        let ids: Vec<_> = min.into_sorted_vec().iter().map(|e| e.0).collect();
+        assert_eq!(ids, vec![3, 1, 2, 4, 5, 7, 0, 6]);
+
+        let mut max = MinHeap::builder().max_order(true).build().unwrap();
+        for item in items {
+            max.insert(item);
+        }
+        max.change_key(7, 5);
+        max.change_key(1, 99);
+        max.assert_valid();
+        assert_eq!(max.peek(), Some((1, 99)));
+        // in a max-heap "decrease" moves towards the top, i.e. grows the key
+        max.decrease_key(0, 200);
+        max.increase_key(1, -3);
+        max.assert_valid();
+        let ids: Vec<_> = max.into_sorted_vec().iter().map(|e| e.0).collect();
+        assert_eq!(ids, vec![0, 6, 5, 4, 3, 2, 7, 1]);
+    }
+
+    #[test]
+    fn test_sorted_drains_follow_ordering() {
+        let items: Vec<_> = (0..20).map(|id| (id, (id * 7 % 20) as i32)).collect();
+        let max_heap = || {
+            let mut mh = MinHeap::builder().max_order(true).build().unwrap();
+            for &item in &items {
+                mh.insert(item);
+            }
+            mh
+        };
+        let descending: Vec<i32> = (0..20).rev().collect();
+        let keys = |v: Vec<(usize, i32)>| v.into_iter().map(|e| e.1).collect::<Vec<_>>();
+
+        assert_eq!(keys(max_heap().into_sorted_vec()), descending);
+        assert_eq!(keys(max_heap().pop_all()), descending);
+        assert_eq!(keys(max_heap().drain_sorted().collect()), descending);
+
+        // custom comparator: even keys first, then by value
+        let mut custom = MinHeap::builder()
+            .comparator(|a: &i32, b: &i32| (a % 2, a).cmp(&(b % 2, b)))
+            .build()
+            .unwrap();
+        for &item in &items {
+            custom.insert(item);
+        }
+        let expected: Vec<i32> = (0..20).step_by(2).chain((1..20).step_by(2)).collect();
+        assert_eq!(keys(custom.clone().into_sorted_vec()), expected);
+        assert_eq!(keys(custom.clone().pop_all()), expected);
+
+        // a partially consumed drain still empties the heap
+        let first: Vec<_> = custom.drain_sorted().take(3).collect();
+        assert_eq!(keys(first), vec![0, 2, 4]);
+        assert!(custom.is_empty());
+    }
+
+    #[test]
+    fn test_decrease_key_handle() {
+        let mut mh = MinHeap::new();
+        let handles: Vec<_> = (0..31)
+            .map(|id| mh.insert_handle((id, 100 + id as i32)))
+            .collect();
+        let slot = |h: &SlotHandle| h.slot.load(atomic::Ordering::Relaxed);
+        assert!(handles.iter().all(|h| mh.slot_of(h.id()) == Some(slot(h))));
+
+        // walk the last leaf up to the root one sift at a time. every item
+        // displaced on the way has its handle patched by the sift
+        let h = &handles[30];
+        for key in [60, 40, 20, 0] {
+            mh.decrease_key_handle(h, key);
+            mh.assert_valid();
+            assert!(handles.iter().all(|h| mh.slot_of(h.id()) == Some(slot(h))));
+        }
+        assert_eq!(slot(h), 0);
+
+        for (h, key) in [(&handles[14], 10), (&handles[6], 5), (&handles[2], 1)] {
+            mh.decrease_key_handle(h, key);
+            mh.assert_valid();
+        }
+        assert!(handles.iter().all(|h| mh.slot_of(h.id()) == Some(slot(h))));
+
+        let popped: Vec<_> = (0..4).map(|_| mh.delete_min().unwrap()).collect();
+        assert_eq!(popped, vec![(30, 0), (2, 1), (6, 5), (14, 10)]);
+
+        // pop_k's sort path rearranges the rest without sifting, leaving
+        // handles behind until their next use corrects them
+        mh.pop_k(14);
+        let stale = handles
+            .iter()
+            .find(|h| mh.contains(h.id()) && mh.slot_of(h.id()) != Some(slot(h)))
+            .unwrap();
+        mh.decrease_key_handle(stale, -5);
+        assert_eq!(mh.peek(), Some((stale.id(), -5)));
+        assert_eq!(slot(stale), 0);
+        mh.assert_valid();
+
+        mh.decrease_key_handle(&handles[30], -10); // no longer present
+        assert_eq!(mh.len(), 13);
+        assert!(!mh.contains(30));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_decrease_key_handle_skips_direction_check() {
+        let mut by_id = MinHeap::new();
+        let mut by_handle = MinHeap::new();
+        let handles: Vec<_> = (0..31)
+            .map(|id| {
+                by_id.insert((id, 100 + id as i32));
+                by_handle.insert_handle((id, 100 + id as i32))
+            })
+            .collect();
+        by_id.reset_comparison_count();
+        by_handle.reset_comparison_count();
+
+        for (id, key) in [(30, 50), (14, 40), (21, 30)] {
+            by_id.decrease_key(id, key);
+            by_handle.decrease_key_handle(&handles[id], key);
+        }
+        assert_eq!(by_id.to_vec(), by_handle.to_vec());
+        assert_eq!(by_handle.comparison_count() + 3, by_id.comparison_count());
+    }
+
+    #[test]
+    fn test_tuple_keys_are_lexicographic() {
+        // (cost, arrival): equal costs fall back to the arrival time
+        let mut mh: MinHeap<(f64, u32)> = MinHeap::new();
+        mh.insert((0, (2.5, 7)));
+        mh.insert((1, (1.0, 9)));
+        mh.insert((2, (2.5, 3)));
+        mh.insert((3, (1.0, 4)));
+        mh.insert((4, (4.0, 0)));
+        assert_eq!(mh.peek(), Some((3, (1.0, 4))));
+
+        // only the secondary component gets smaller
+        mh.decrease_key(1, (1.0, 2));
+        assert_eq!(mh.peek(), Some((1, (1.0, 2))));
+        // smaller primary wins regardless of the secondary
+        mh.decrease_key(4, (0.5, 99));
+        mh.assert_valid();
+
+        let ids: Vec<_> = std::iter::from_fn(|| mh.delete_min())
+            .map(|e| e.0)
+            .collect();
+        assert_eq!(ids, vec![4, 1, 3, 2, 0]);
+    }
+
+    #[test]
+    fn test_lazy_delete() {
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..100 {
+            mh.insert((id, (id * 13 % 100) as i32));
+        }
+
+        // tombstone 40 interior ids, below the compaction threshold
+        let doomed: Vec<usize> = (0..100).filter(|id| id % 5 < 2).collect();
+        for &id in &doomed {
+            assert_eq!(mh.delete(id), Some((id, (id * 13 % 100) as i32)));
+            assert_eq!(mh.delete(id), None);
+            assert!(!mh.contains(id));
+        }
+        mh.assert_valid();
+        assert_eq!(mh.len(), 60);
+        assert!(mh.dead_count > 0 && mh.heap.len() > 60);
+
+        // reinserting a tombstoned id replaces the tombstone
+        mh.insert((0, 1000));
+        assert_eq!(mh.len(), 61);
+        mh.assert_valid();
+
+        // pushing the dead fraction over half compacts the array
+        for id in (2..100).step_by(5) {
+            mh.delete(id);
+        }
+        mh.assert_valid();
+        assert_eq!(mh.len(), 41);
+        assert!(mh.heap.len() < 61);
+
+        let mut expected: Vec<_> = (0..100)
+            .filter(|id| id % 5 >= 3)
+            .map(|id| (id, (id * 13 % 100) as i32))
+            .collect();
+        expected.push((0, 1000));
+        expected.sort_by_key(|e| e.1);
+        let popped: Vec<_> = std::iter::from_fn(|| mh.delete_min()).collect();
+        assert_eq!(popped, expected);
+    }
+
+    #[test]
+    fn test_decrease_key_returning() {
+        let mut mh = MinHeap::build_heap(vec![(0, 8.5), (1, 3.0), (4, 6.25)]);
+        assert_eq!(mh.decrease_key_returning(0, 2.0), Some(8.5));
+        assert_eq!(mh.decrease_key_returning(0, 1.0), Some(2.0));
+        assert_eq!(mh.peek(), Some((0, 1.0)));
+
+        assert_eq!(mh.decrease_key_returning(2, 0.0), None);
+        assert_eq!(mh.decrease_key_returning(99, 0.0), None);
+        mh.delete_min();
+        assert_eq!(mh.decrease_key_returning(0, 0.5), None);
+        mh.assert_valid();
+    }
+
+    #[test]
+    fn test_apply_decreases() {
+        let items: Vec<_> = (0..64)
+            .map(|id| (id, 1000 + (id * 29 % 64) as i64))
+            .collect();
+        let updates: Vec<_> = (0..64)
+            .filter(|id| id % 3 == 0)
+            .map(|id| (id, (id * 17 % 40) as i64))
+            .collect();
+
+        let mut batched = MinHeap::build_heap(items.clone());
+        batched.apply_decreases(&updates).unwrap();
+        batched.assert_valid();
+
+        let mut one_by_one = MinHeap::build_heap(items);
+        for &(id, key) in &updates {
+            one_by_one.decrease_key(id, key);
+        }
+        assert!(batched == one_by_one);
+        let keys = |h: MinHeap<i64>| {
+            h.into_sorted_vec()
+                .into_iter()
+                .map(|e| e.1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(batched.clone()), keys(one_by_one));
+
+        // nothing is written when any update is invalid
+        let before = batched.clone();
+        assert_eq!(
+            batched.apply_decreases(&[(1, 0), (3, 5000)]),
+            Err(HeapError::KeyNotSmaller(3))
+        );
+        assert_eq!(
+            batched.apply_decreases(&[(1, 0), (64, 0)]),
+            Err(HeapError::NotPresent(64))
+        );
+        assert!(batched == before);
+        assert_eq!(batched.to_vec(), before.to_vec());
     }
 
     #[test]
-    fn test_delete_min_basic() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.insert((0, 20));
-        mh.insert((1, 10));
-        mh.insert((2, 30));
-        let min = mh.delete_min().unwrap();
-        assert_eq!(min, (1, 10));
-        assert_eq!(*mh.get_min().unwrap(), (0, 20));
-        assert_eq!(mh.positions[0], 0);
-        assert_eq!(mh.positions[2], 1);
+    fn test_drain_while() {
+        let mut mh = MinHeap::new();
+        assert!(mh.drain_while(|_| true).is_empty());
+
+        for (id, t) in [(0, 7), (1, 3), (2, 12), (3, 5), (4, 3), (5, 9)] {
+            mh.insert((id, t));
+        }
+        assert!(mh.drain_while(|&t| t < 3).is_empty());
+        assert_eq!(mh.len(), 6);
+
+        let due: Vec<_> = mh.drain_while(|&t| t <= 7).iter().map(|e| e.1).collect();
+        assert_eq!(due, vec![3, 3, 5, 7]);
+        assert_eq!(mh.peek(), Some((5, 9)));
+        mh.assert_valid();
     }
 
     #[test]
-    fn test_delete_min_empty() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        assert!(mh.delete_min().is_none());
+    fn test_try_ops_leave_heap_unchanged_on_nan() {
+        let items: Vec<_> = (0..15).map(|id| (id, id as f64 * 1.5)).collect();
+        let mut mh = MinHeap::build_heap(items);
+        // NaN != NaN, so compare bit patterns
+        let snapshot = |h: &MinHeap<f64>| {
+            h.heap
+                .iter()
+                .map(|e| (e.0, e.1.to_bits()))
+                .collect::<Vec<_>>()
+        };
+
+        let before = snapshot(&mh);
+        assert_eq!(
+            mh.try_decrease_key(9, f64::NAN),
+            Err(HeapError::Incomparable)
+        );
+        assert_eq!(
+            mh.try_decrease_key(9, 20.0),
+            Err(HeapError::KeyNotSmaller(9))
+        );
+        assert_eq!(mh.try_decrease_key(40, 0.0), Err(HeapError::NotPresent(40)));
+        assert_eq!(snapshot(&mh), before);
+
+        // a NaN that is only met halfway up the sift path
+        mh.heap[1].1 = f64::NAN;
+        let before = snapshot(&mh);
+        assert_eq!(mh.try_decrease_key(7, 1.0), Err(HeapError::Incomparable));
+        assert_eq!(snapshot(&mh), before);
+        mh.heap[1].1 = 1.5;
+
+        // a NaN in the last leaf is compared as soon as it moves to the root
+        mh.heap[14].1 = f64::NAN;
+        let before = snapshot(&mh);
+        assert_eq!(mh.try_delete_min(), Err(HeapError::Incomparable));
+        assert_eq!(snapshot(&mh), before);
+        assert_eq!(mh.len(), 15);
+
+        mh.heap[14].1 = 21.0;
+        assert_eq!(mh.try_decrease_key(7, 0.5), Ok(()));
+        assert_eq!(mh.try_delete_min(), Ok(Some((0, 0.0))));
+        assert_eq!(mh.try_delete_min(), Ok(Some((7, 0.5))));
+        mh.assert_valid();
+
+        // lazy mode: the tombstone forces a compaction before the pop, which
+        // must not panic or reshuffle on the NaN either
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..10 {
+            mh.insert((id, id as f64));
+        }
+        mh.delete(7);
+        *mh.key_mut(5).unwrap() = f64::NAN;
+        let before = snapshot(&mh);
+        assert_eq!(mh.try_delete_min(), Err(HeapError::Incomparable));
+        assert_eq!(snapshot(&mh), before);
+        assert!(!mh.contains(7) && mh.len() == 9);
+
+        *mh.key_mut(5).unwrap() = 5.0;
+        assert_eq!(mh.try_delete_min(), Ok(Some((0, 0.0))));
+        mh.assert_valid();
+        assert!(!mh.contains(7));
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_bubble_up_manual() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.heap = vec![(0, 10), (1, 5)];
-        mh.positions = vec![0, 1];
-        mh.bubble_up(1);
-        assert_eq!(mh.heap, vec![(1, 5), (0, 10)]);
-        assert_eq!(mh.positions, vec![1, 0]);
+    fn test_op_counters_sift_steps() {
+        let n = 1024usize;
+        let mut mh = MinHeap::new();
+        // descending keys: every insert bubbles all the way up
+        for id in 0..n {
+            mh.insert((id, n - id));
+        }
+        let after_inserts = mh.op_counters().sift_steps;
+        let depth = (usize::BITS - n.leading_zeros()) as u64;
+        assert!(after_inserts <= n as u64 * depth);
+
+        while mh.delete_min().is_some() {}
+        let ops = mh.op_counters();
+        assert!(
+            ops.sift_steps - after_inserts <= n as u64 * depth,
+            "{ops:?}"
+        );
+        assert!(ops.comparisons >= ops.sift_steps);
+        assert_eq!(ops.links, 0);
     }
 
     #[test]
-    fn test_bubble_down_manual() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.heap = vec![(0, 50), (1, 20), (2, 30)];
-        mh.positions = vec![0, 1, 2];
-        mh.bubble_down(0);
-        assert_eq!(mh.heap[0], (1, 20));
-        assert_eq!(mh.positions[1], 0);
-        assert_eq!(mh.positions[0], 1);
+    fn test_reorder_by() {
+        let mut mh = MinHeap::builder()
+            .comparator(|a: &(u32, u32), b: &(u32, u32)| a.0.cmp(&b.0))
+            .build()
+            .unwrap();
+        for (id, key) in [
+            (0, (3, 1)),
+            (1, (1, 4)),
+            (2, (4, 0)),
+            (3, (2, 3)),
+            (4, (0, 2)),
+        ] {
+            mh.insert((id, key));
+        }
+        assert_eq!(mh.delete_min(), Some((4, (0, 2))));
+
+        mh.reorder_by(|a, b| a.1.cmp(&b.1));
+        mh.assert_valid();
+        let ids: Vec<_> = std::iter::from_fn(|| mh.delete_min())
+            .map(|e| e.0)
+            .collect();
+        assert_eq!(ids, vec![2, 0, 3, 1]);
     }
 
     #[test]
-    fn test_mixed_operations() {
-        let mut mh: MinHeap<i32> = MinHeap::new();
-        mh.insert((3, 15));
-        mh.insert((2, 25));
-        mh.insert((5, 5));
-        assert_eq!(*mh.get_min().unwrap(), (5, 5));
-        let order: Vec<_> = (0..3).map(|_| mh.delete_min().unwrap()).collect();
-        assert_eq!(order, vec![(5, 5), (3, 15), (2, 25)]);
-        assert!(mh.delete_min().is_none());
+    fn test_pop_log() {
+        let mut mh = MinHeap::from_keys(vec![40, 10, 30, 20, 50, 60]);
+        mh.delete_min(); // before enabling, not logged
+
+        mh.enable_pop_log();
+        let mut popped = vec![mh.delete_min().unwrap(), mh.delete_min().unwrap()];
+        popped.extend(mh.pop_k(2));
+        assert_eq!(mh.pop_log(), popped.as_slice());
+        assert_eq!(popped, vec![(3, 20), (2, 30), (0, 40), (4, 50)]);
+
+        assert_eq!(mh.disable_pop_log(), popped);
+        mh.delete_min();
+        assert!(mh.pop_log().is_empty());
     }
 
     #[test]
-    fn test_build_heap() {
-        let items = vec![(2, 50), (0, 10), (3, 20), (1, 5)];
-        let mut mh = MinHeap::build_heap(items);
-        assert_eq!(*mh.get_min().unwrap(), (1, 5));
-        assert_eq!(mh.positions[1], 0);
-        assert_eq!(mh.heap.len(), 4);
-        let order: Vec<_> = (0..4).map(|_| mh.delete_min().unwrap()).collect();
-        assert_eq!(order, vec![(1, 5), (0, 10), (3, 20), (2, 50)]);
-        assert!(mh.delete_min().is_none());
+    fn test_build_heap_sorted_fast_path() {
+        let sorted: Vec<_> = (0..200).map(|id| (id, id as i32 / 3)).collect();
+        let mut reversed = sorted.clone();
+        reversed.reverse();
+        let mut scrambled = sorted.clone();
+        scrambled.sort_by_key(|e| (e.0 * 89) % 200);
+
+        let heaps: Vec<_> = [&sorted, &reversed, &scrambled]
+            .into_iter()
+            .map(|items| MinHeap::build_heap(items.clone()))
+            .collect();
+        for h in &heaps {
+            h.assert_valid();
+            assert!(*h == heaps[0]);
+        }
+        // sorted input is kept as is
+        assert_eq!(heaps[0].to_vec(), sorted);
+
+        #[cfg(feature = "metrics")]
+        {
+            // only the n - 1 comparisons of the scan, no sift sweep
+            assert_eq!(heaps[0].comparison_count(), 199);
+            assert_eq!(heaps[0].op_counters().sift_steps, 0);
+            assert!(heaps[1].op_counters().sift_steps > 0);
+        }
+    }
+
+    #[test]
+    fn test_delete_max() {
+        let mut mh = MinHeap::new();
+        assert_eq!(mh.delete_max(), None);
+
+        let keys: Vec<i32> = (0..60).map(|id| (id * 41) % 97).collect();
+        let mut oracle: Vec<(i32, usize)> = Vec::new();
+        for (id, &k) in keys.iter().enumerate() {
+            mh.insert((id, k));
+            oracle.push((k, id));
+        }
+        oracle.sort();
+
+        for round in 0..40 {
+            if round % 3 == 0 {
+                let (k, id) = oracle.remove(0);
+                assert_eq!(mh.delete_min(), Some((id, k)));
+            } else {
+                let (k, id) = oracle.pop().unwrap();
+                assert_eq!(mh.delete_max(), Some((id, k)));
+            }
+            if round % 10 == 5 {
+                // a new overall max must be picked up by the cached hint
+                let id = 100 + round;
+                mh.insert((id, 1000 + round as i32));
+                oracle.push((1000 + round as i32, id));
+            }
+            mh.assert_valid();
+        }
+        assert_eq!(mh.len(), oracle.len());
+    }
+
+    #[test]
+    fn test_try_get_min_poisoned() {
+        let mut mh: MinHeap<f64> = MinHeap::new();
+        assert_eq!(mh.try_get_min(), Ok(None));
+        mh.insert((0, 1.0));
+        mh.insert((1, 2.0));
+
+        assert_eq!(
+            mh.try_decrease_key(1, f64::NAN),
+            Err(HeapError::Incomparable)
+        );
+        assert!(mh.is_poisoned());
+        assert_eq!(mh.try_get_min(), Err(HeapError::Incomparable));
+        // get_min itself is unaffected
+        assert_eq!(mh.get_min(), Some(&(0, 1.0)));
+
+        // structurally empty, but still poisoned
+        mh.delete_min();
+        mh.delete_min();
+        assert!(mh.is_empty());
+        assert_eq!(mh.try_get_min(), Err(HeapError::Incomparable));
+
+        let copy = mh.clone();
+        assert!(copy.is_poisoned());
+
+        mh.clear();
+        assert_eq!(mh.try_get_min(), Ok(None));
+
+        // other errors don't poison
+        mh.insert((0, 1.0));
+        assert_eq!(
+            mh.try_decrease_key(0, 5.0),
+            Err(HeapError::KeyNotSmaller(0))
+        );
+        assert_eq!(mh.try_get_min(), Ok(Some(&(0, 1.0))));
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let mut mh = MinHeap::new();
+        assert!(mh.as_slice().is_empty());
+        for (id, k) in [(0, 40), (1, 10), (2, 30), (3, 20)] {
+            mh.insert((id, k));
+        }
+        mh.decrease_key(2, 5);
+        mh.delete_min();
+        mh.delete(3);
+
+        let slice = mh.as_slice();
+        assert_eq!(slice.len(), mh.len());
+        assert_eq!(slice[0], *mh.get_min().unwrap());
+        let mut ids: Vec<_> = slice.iter().map(|e| e.0).collect();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(slice, mh.to_vec().as_slice());
+    }
+
+    #[test]
+    fn test_delete_min_into() {
+        let items: Vec<(usize, i32)> = (0..200).map(|id| (id, (id as i32 * 37) % 101)).collect();
+        let mut a = MinHeap::build_heap(items.clone());
+        let mut b = MinHeap::build_heap(items);
+
+        let mut slot = (usize::MAX, -1);
+        while a.delete_min_into(&mut slot) {
+            assert_eq!(Some(slot), b.delete_min());
+        }
+        assert!(b.is_empty());
+
+        // empty: out is left alone
+        let last = slot;
+        assert!(!a.delete_min_into(&mut slot));
+        assert_eq!(slot, last);
+    }
+
+    #[test]
+    fn test_get_min_lazy_skips_tombstones() {
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..10 {
+            mh.insert((id, id as i32 * 10));
+        }
+        // tombstone the min and the next two, plus one deep entry
+        mh.delete(0);
+        mh.delete(1);
+        mh.delete(7);
+        assert_eq!(mh.get_min(), Some(&(2, 20)));
+        mh.delete(2);
+        assert_eq!(mh.get_min(), Some(&(3, 30)));
+        assert_eq!(mh.delete_min(), Some((3, 30)));
+        assert_eq!(mh.get_min(), Some(&(4, 40)));
+        mh.assert_valid();
+    }
+
+    #[test]
+    fn test_min_slot_eager() {
+        let mut mh = MinHeap::new();
+        assert_eq!(mh.min_slot(), None);
+        for (id, k) in [(0, 5), (1, 3), (2, 8), (3, 1)] {
+            mh.insert((id, k));
+            assert_eq!(mh.min_slot(), Some(0));
+        }
+        mh.decrease_key(2, 0);
+        mh.delete(1);
+        assert_eq!(mh.min_slot(), Some(0));
+        assert_eq!(mh.get_min(), Some(&mh.heap[0]));
+        assert_eq!(mh.get_min(), Some(&(2, 0)));
+    }
+
+    #[test]
+    fn test_with_min_and_pop_and() {
+        let mut mh: MinHeap<Vec<u32>> = MinHeap::new();
+        assert_eq!(mh.with_min(|_, k| k.len()), None);
+        mh.insert((0, vec![3; 1000]));
+        mh.insert((1, vec![1; 500]));
+        mh.insert((2, vec![2; 10]));
+
+        let sum = mh.with_min(|id, k| (id, k.iter().sum::<u32>()));
+        assert_eq!(sum, Some((1, 500)));
+        assert_eq!(mh.len(), 3);
+
+        assert_eq!(mh.pop_and(|id, k| (id, k.len())), Some((1, 500)));
+        assert_eq!(mh.pop_and(|id, k| (id, k.len())), Some((2, 10)));
+        assert_eq!(mh.len(), 1);
+        mh.assert_valid();
+    }
+
+    #[test]
+    fn test_new_by_key() {
+        #[derive(Clone, Debug, PartialEq, PartialOrd)]
+        struct Job {
+            name: &'static str,
+            deadline: u32,
+        }
+
+        let mut mh = MinHeap::new_by_key(|j: &Job| j.deadline);
+        for (id, (name, deadline)) in [("a", 30), ("b", 10), ("c", 20), ("d", 5)]
+            .into_iter()
+            .enumerate()
+        {
+            mh.insert((id, Job { name, deadline }));
+        }
+        // not the derived order, which would compare names first
+        mh.decrease_key(
+            0,
+            Job {
+                name: "a",
+                deadline: 1,
+            },
+        );
+        let names: Vec<_> = std::iter::from_fn(|| mh.delete_min().map(|(_, j)| j.name)).collect();
+        assert_eq!(names, vec!["a", "d", "b", "c"]);
+    }
+
+    #[test]
+    fn test_reset_to() {
+        let mut mh = MinHeap::new();
+        let (mut cap, mut pos_cap) = (mh.heap.capacity(), mh.positions.capacity());
+        for trial in 0..20u64 {
+            let items: Vec<(usize, u64)> = (0..50)
+                .map(|id| (id, (id as u64 * 7919 + trial * 31) % 97))
+                .collect();
+            let mut expected: Vec<u64> = items.iter().map(|e| e.1).collect();
+            expected.sort();
+
+            mh.reset_to(items);
+            mh.assert_valid();
+            assert!(mh.heap.capacity() >= cap && mh.positions.capacity() >= pos_cap);
+            (cap, pos_cap) = (mh.heap.capacity(), mh.positions.capacity());
+
+            let keys: Vec<_> = std::iter::from_fn(|| mh.delete_min().map(|e| e.1)).collect();
+            assert_eq!(keys, expected);
+        }
+    }
+
+    #[test]
+    fn test_large_ids() {
+        let mut mh = MinHeap::new();
+        mh.insert((1 << 20, 5));
+        mh.insert((3, 9));
+        assert_eq!(mh.delete_min(), Some((1 << 20, 5)));
+
+        assert_eq!(
+            MinHeap::try_build_heap(vec![(0, 1), (usize::MAX, 2)]).err(),
+            Some(HeapError::IdTooLarge(usize::MAX))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "id too large")]
+    fn test_insert_max_id_panics() {
+        let mut mh = MinHeap::new();
+        mh.insert((usize::MAX, 1));
+    }
+
+    #[test]
+    fn test_relax_and_extract() {
+        let base: Vec<(usize, i64)> = (0..64)
+            .map(|id| (id, 1000 + (id as i64 * 37) % 101))
+            .collect();
+
+        // a small batch (sifts) and a large one (heapify), both with
+        // non-improving and absent entries mixed in
+        let small = vec![(5, 3), (9, 2000), (70, 0), (12, 7)];
+        let large: Vec<(usize, i64)> = (0..64)
+            .map(|id| (id, 900 + (id as i64 * 53) % 211))
+            .collect();
+
+        for updates in [small, large] {
+            let mut batched = MinHeap::build_heap(base.clone());
+            let mut single = MinHeap::build_heap(base.clone());
+            for &(id, k) in &updates {
+                if single.slot_of(id).is_some_and(|p| k < single.heap[p].1) {
+                    single.decrease_key(id, k);
+                }
+            }
+            assert_eq!(batched.relax_and_extract(&updates), single.delete_min());
+            batched.assert_valid();
+            assert!(batched == single);
+        }
+        assert_eq!(MinHeap::<i64>::new().relax_and_extract(&[(0, 1)]), None);
+    }
+
+    #[test]
+    fn test_raw_parts_round_trip() {
+        let mut mh = MinHeap::new();
+        for id in 0..30 {
+            mh.insert((id, (id * 17) % 31));
+        }
+        mh.decrease_key(20, 0);
+        mh.delete_min();
+        let expected = mh.clone().into_sorted_vec();
+
+        let (heap, positions) = mh.into_raw_parts();
+        let restored = MinHeap::try_from_raw_parts(heap.clone(), positions.clone()).unwrap();
+        restored.assert_valid();
+        assert_eq!(restored.as_slice(), heap.as_slice());
+        assert_eq!(restored.into_sorted_vec(), expected);
+
+        let unchecked = unsafe { MinHeap::from_raw_parts(heap, positions) };
+        unchecked.assert_valid();
+    }
+
+    #[test]
+    fn test_raw_parts_rejects_bad_layout() {
+        let (heap, positions) = MinHeap::from_keys(vec![3, 1, 2]).into_raw_parts();
+
+        let mut swapped = positions.clone();
+        swapped.swap(0, 1);
+        assert!(matches!(
+            MinHeap::try_from_raw_parts(heap.clone(), swapped),
+            Err(HeapError::InvalidLayout(_))
+        ));
+
+        let mut extra = positions.clone();
+        extra.push(0);
+        assert!(MinHeap::try_from_raw_parts(heap.clone(), extra).is_err());
+
+        // reversed array with a matching positions table: order is broken
+        let reversed: Vec<_> = heap.iter().rev().cloned().collect();
+        let mut pos = vec![usize::MAX; 3];
+        for (idx, (id, _)) in reversed.iter().enumerate() {
+            pos[*id] = idx;
+        }
+        assert_eq!(
+            MinHeap::try_from_raw_parts(reversed, pos).err(),
+            Some(HeapError::InvalidLayout("heap order is violated"))
+        );
+    }
+
+    #[test]
+    fn test_drain_filter() {
+        let mut mh = MinHeap::build_heap((0..100).map(|id| (id, (id * 37) % 101)).collect());
+
+        let mut odd = mh.drain_filter(|id, _| id % 2 == 1);
+        odd.sort();
+        let expected: Vec<_> = (0..100)
+            .filter(|id| id % 2 == 1)
+            .map(|id| (id, (id * 37) % 101))
+            .collect();
+        assert_eq!(odd, expected);
+
+        assert_eq!(mh.len(), 50);
+        mh.assert_valid();
+        assert!((0..100).all(|id| mh.contains(id) == (id % 2 == 0)));
+        assert!(mh.drain_filter(|_, _| false).is_empty());
+
+        let keys: Vec<_> = std::iter::from_fn(|| mh.delete_min().map(|e| e.1)).collect();
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_key_mut_then_heapify() {
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..40 {
+            mh.insert((id, 100 + id as i32));
+        }
+        mh.delete(3);
+        mh.delete(17);
+
+        // edits that break heap order in both directions
+        *mh.key_mut(39).unwrap() = 1;
+        *mh.key_mut(0).unwrap() = 500;
+        *mh.key_mut(25).unwrap() = 2;
+        *mh.key_mut(12).unwrap() = 499;
+        assert!(mh.key_mut(3).is_none());
+        mh.heapify();
+        mh.assert_valid();
+
+        let order: Vec<_> = std::iter::from_fn(|| mh.delete_min()).collect();
+        assert_eq!(order[..2], [(39, 1), (25, 2)]);
+        assert_eq!(order[order.len() - 2..], [(12, 499), (0, 500)]);
+        assert_eq!(order.len(), 38);
+        assert!(order.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let mut mh = MinHeap::new();
+        assert_eq!(mh.insert_batch(&[]), None);
+        for id in 0..100 {
+            mh.insert((id, 1000 + id as i32));
+        }
+
+        // small batch: sifted in one by one
+        assert_eq!(mh.insert_batch(&[(100, 5000), (101, 7)]), Some((101, 7)));
+        mh.assert_valid();
+
+        // large batch: appended and heapified, ids past the current table
+        let big: Vec<_> = (200..500).map(|id| (id, 2000 - id as i32)).collect();
+        assert_eq!(mh.insert_batch(&big), Some((101, 7)));
+        assert_eq!(mh.insert_batch(&[(900, -1)]), Some((900, -1)));
+        mh.assert_valid();
+        assert_eq!(mh.len(), 403);
     }
 
     #[test]
@@ -325,6 +3460,38 @@ mod tests {
     }
 
     #[test]
+    fn test_decrease_key_by() {
+        let mut mh: MinHeap<i32> = MinHeap::new();
+        mh.insert((0, 100));
+        mh.insert((1, 200));
+        mh.insert((2, 300));
+        mh.decrease_key_by(2, 250);
+        assert_eq!(*mh.get_min().unwrap(), (2, 50));
+        assert_eq!(mh.positions[2], 0);
+        mh.decrease_key_by(1, 10);
+        assert_eq!(mh.heap[mh.positions[1]], (1, 190));
+
+        // towards the top of a max-heap means a negative delta
+        let mut max = MinHeap::builder().max_order(true).build().unwrap();
+        for (id, key) in [(0, 100), (1, 200), (2, 300)] {
+            max.insert((id, key));
+        }
+        max.decrease_key_by(0, -250);
+        assert_eq!(max.peek(), Some((0, 350)));
+        max.assert_valid();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "towards the top")]
+    fn test_decrease_key_by_checks_active_order() {
+        let mut max = MinHeap::builder().max_order(true).build().unwrap();
+        max.insert((0, 100));
+        max.decrease_key_by(0, 10);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn test_float_keys_basic() {
         let mut mh: MinHeap<f64> = MinHeap::new();
         mh.insert((0, 3.14));
@@ -358,4 +3525,264 @@ mod tests {
         assert_eq!(first, (1, 5.0));
         assert_eq!(second, (0, 10.0));
     }
+
+    #[test]
+    fn test_string_keys() {
+        let mut mh: MinHeap<String> = MinHeap::new();
+        for (id, word) in ["pear", "apple", "fig", "banana"].iter().enumerate() {
+            mh.insert((id, word.to_string()));
+        }
+        mh.decrease_key(0, "cherry".to_string());
+
+        let mut seq = Vec::new();
+        while let Some((_, key)) = mh.delete_min() {
+            seq.push(key);
+        }
+        assert_eq!(seq, vec!["apple", "banana", "cherry", "fig"]);
+    }
+
+    #[test]
+    fn test_peek_two_matches_two_pops() {
+        for trial in 0..40u64 {
+            let n = (trial % 13) as usize;
+            let mut mh = if trial % 2 == 0 {
+                MinHeap::new()
+            } else {
+                MinHeap::new_lazy()
+            };
+            for id in 0..n + 8 {
+                mh.insert((id, (id as u64 * 7919 + trial * 31) % 23));
+            }
+            // tombstones below the root in lazy mode
+            for id in (1..n + 8).step_by(3) {
+                mh.delete(id);
+            }
+
+            let (a, b) = mh.peek_two();
+            let first = mh.delete_min();
+            let second = mh.delete_min();
+            // ties may pick either id, compare keys
+            assert_eq!(a.map(|e| e.1), first.map(|e| e.1));
+            assert_eq!(b.map(|e| e.1), second.map(|e| e.1));
+            for e in first.into_iter().chain(second) {
+                mh.insert(e);
+            }
+            mh.assert_valid();
+        }
+
+        let mut mh = MinHeap::new();
+        assert_eq!(mh.peek_two(), (None, None));
+        mh.insert((4, 'b'));
+        assert_eq!(mh.peek_two(), (Some((4, 'b')), None));
+        mh.insert((2, 'a'));
+        assert_eq!(mh.peek_two(), (Some((2, 'a')), Some((4, 'b'))));
+    }
+
+    #[test]
+    fn test_transfer_between_heaps() {
+        let mut hi = MinHeap::new_lazy();
+        let mut lo = MinHeap::new();
+        for id in 0..20 {
+            hi.insert((id, 100 + id as u32));
+        }
+        for id in 20..25 {
+            lo.insert((id, id as u32));
+        }
+
+        assert_eq!(transfer(&mut hi, &mut lo, 7, 3), Ok(107));
+        assert!(!hi.contains(7) && lo.contains(7));
+        assert_eq!(lo.get_min(), Some(&(7, 3)));
+        assert_eq!(hi.len(), 19);
+        hi.assert_valid();
+        lo.assert_valid();
+
+        // absent in from, already in to: nothing moves
+        assert_eq!(
+            transfer(&mut hi, &mut lo, 7, 1),
+            Err(HeapError::NotPresent(7))
+        );
+        assert_eq!(transfer(&mut hi, &mut lo, 0, 1), Ok(100));
+        assert_eq!(transfer(&mut lo, &mut hi, 21, 0), Ok(21));
+        hi.insert((20, 0));
+        assert_eq!(
+            transfer(&mut lo, &mut hi, 20, 0),
+            Err(HeapError::DuplicateId(20))
+        );
+        assert!(lo.contains(20));
+        assert_eq!(hi.len() + lo.len(), 26);
+        hi.assert_valid();
+        lo.assert_valid();
+
+        // a full target refuses before anything leaves from
+        let mut full = MinHeap::with_max_len(1);
+        full.insert((30, 0));
+        assert_eq!(
+            transfer(&mut lo, &mut full, 22, 0),
+            Err(HeapError::CapacityExceeded)
+        );
+        assert!(lo.contains(22) && !full.contains(22));
+        assert_eq!(full.len(), 1);
+    }
+
+    #[test]
+    fn test_max_len_refuses_growth() {
+        let mut mh = MinHeap::with_max_len(4);
+        for id in 0..4 {
+            assert_eq!(mh.try_insert((id, 10 - id as i32)), Ok(()));
+        }
+        let before = mh.clone();
+        assert_eq!(mh.try_insert((9, -5)), Err(HeapError::CapacityExceeded));
+        assert!(mh == before && !mh.contains(9));
+        assert_eq!(mh.get_min(), Some(&(3, 7)));
+        assert!(matches!(
+            mh.apply(Op::Insert(9, -5)),
+            OpResult::Failed(HeapError::CapacityExceeded)
+        ));
+
+        // a pop frees a slot again
+        mh.delete_min();
+        assert_eq!(mh.try_insert((9, -5)), Ok(()));
+        assert_eq!(mh.try_insert((9, 1)), Err(HeapError::DuplicateId(9)));
+        mh.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity exceeded")]
+    fn test_max_len_insert_panics() {
+        let mut mh = MinHeap::with_max_len(1);
+        mh.insert((0, 1));
+        mh.insert((1, 2));
+    }
+
+    #[test]
+    fn test_push_assigns_ids() {
+        let mut mh = MinHeap::new();
+        let ids: Vec<usize> = [40, 10, 30, 20].into_iter().map(|k| mh.push(k)).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+
+        mh.decrease_key(ids[2], 5);
+        assert_eq!(mh.delete_min(), Some((2, 5)));
+        // the popped id is reused, a hand-inserted one is skipped
+        assert_eq!(mh.push(50), 2);
+        mh.insert((4, 1));
+        assert_eq!(mh.push(60), 5);
+        mh.delete(0);
+        assert_eq!(mh.push(70), 0);
+        assert_eq!(mh.len(), 6);
+        mh.assert_valid();
+
+        mh.clear();
+        assert_eq!(mh.push(1), 0);
+    }
+
+    #[test]
+    fn test_draining_iter_shrinks() {
+        let n = 5000;
+        let mut mh = MinHeap::new();
+        for id in 0..n {
+            mh.insert((id, (id as u64 * 7919) % 10007));
+        }
+        let start_cap = mh.heap.capacity();
+        let mut it = mh.into_draining_iter();
+        assert_eq!(it.len(), n);
+
+        let mut last = 0;
+        let mut shrunk_at = None;
+        for step in 0..n {
+            let (_, key) = it.next().unwrap();
+            assert!(key >= last);
+            last = key;
+            if shrunk_at.is_none() && it.heap.heap.capacity() < start_cap {
+                shrunk_at = Some(step);
+            }
+        }
+        // first shrink when 4096 entries are left
+        assert_eq!(shrunk_at, Some(n - 4096 - 1));
+        assert!(it.next().is_none());
+        assert!(it.heap.heap.capacity() <= 1);
+    }
+
+    #[test]
+    fn test_level_order() {
+        let mut mh = MinHeap::new();
+        for id in 0..7 {
+            mh.insert((id, 70 - id as i32));
+        }
+        let sizes: Vec<(usize, usize)> = mh.level_order().map(|(l, s)| (l, s.len())).collect();
+        assert_eq!(sizes, vec![(0, 1), (1, 2), (2, 4)]);
+        let flat: Vec<_> = mh
+            .level_order()
+            .flat_map(|(_, s)| s.iter().cloned())
+            .collect();
+        assert_eq!(flat, mh.as_slice());
+
+        mh.insert((7, 0));
+        let sizes: Vec<usize> = mh.level_order().map(|(_, s)| s.len()).collect();
+        assert_eq!(sizes, vec![1, 2, 4, 1]);
+        assert_eq!(MinHeap::<i32>::new().level_order().count(), 0);
+    }
+
+    #[test]
+    fn test_merge_keep_min() {
+        let mut a = MinHeap::new_lazy();
+        for id in 0..30 {
+            a.insert((id, 100 + (id as u32 * 13) % 40));
+        }
+        a.delete(4);
+        let mut b = MinHeap::new();
+        for id in (0..50).step_by(2) {
+            b.insert((id, 90 + (id as u32 * 29) % 50));
+        }
+
+        let mut want = vec![None; 50];
+        for (id, key) in a.as_slice().iter().filter(|e| a.contains(e.0)) {
+            want[*id] = Some(*key);
+        }
+        for (id, key) in b.as_slice() {
+            want[*id] = Some(want[*id].map_or(*key, |k: u32| k.min(*key)));
+        }
+
+        a.merge_keep_min(b);
+        a.assert_valid();
+        let mut got = vec![None; 50];
+        let mut last = 0;
+        while let Some((id, key)) = a.delete_min() {
+            assert!(key >= last && got[id].is_none());
+            last = key;
+            got[id] = Some(key);
+        }
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_iter_sorted_prefix() {
+        let mut mh = MinHeap::new_lazy();
+        for id in 0..1000 {
+            mh.insert((id, (id as u64 * 7919) % 1009));
+        }
+        for id in (0..1000).step_by(7) {
+            mh.delete(id);
+        }
+        let before = mh.as_slice().to_vec();
+
+        let first: Vec<(usize, u64)> = mh.iter_sorted().take(5).collect();
+        let mut all: Vec<(usize, u64)> = mh
+            .as_slice()
+            .iter()
+            .filter(|e| mh.contains(e.0))
+            .cloned()
+            .collect();
+        all.sort_by_key(|e| e.1);
+        assert_eq!(
+            first.iter().map(|e| e.1).collect::<Vec<_>>(),
+            all[..5].iter().map(|e| e.1).collect::<Vec<_>>()
+        );
+        assert_eq!(mh.as_slice(), &before[..]);
+
+        // the full walk matches popping
+        let walked: Vec<u64> = mh.iter_sorted().map(|e| e.1).collect();
+        let popped: Vec<u64> = std::iter::from_fn(|| mh.delete_min().map(|e| e.1)).collect();
+        assert_eq!(walked, popped);
+        assert_eq!(mh.iter_sorted().next(), None);
+    }
 }
@@ -0,0 +1,369 @@
+use std::cmp::Ordering;
+
+// double-ended priority queue: the classic min-max heap layout, where even
+// levels (root = level 0) are min levels and odd levels are max levels. a
+// node on a min level is <= everything below it, a node on a max level is
+// >= everything below it, so the min is the root and the max is the larger
+// of the root's children. same (id, key) + positions design as MinHeap;
+// insert, both pops and key changes are O(log n).
+//
+// keys are compared with PartialOrd; an incomparable key (NaN) never counts
+// as smaller or larger, so it just stays wherever it lands
+#[derive(Clone)]
+pub struct MinMaxHeap<K> {
+    heap: Vec<(usize, K)>,
+    // id -> index in heap, usize::MAX if absent
+    positions: Vec<usize>,
+}
+
+impl<K: PartialOrd + Clone> Default for MinMaxHeap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone> MinMaxHeap<K> {
+    pub fn new() -> Self {
+        MinMaxHeap {
+            heap: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn clear(&mut self) {
+        for &(id, _) in &self.heap {
+            self.positions[id] = usize::MAX;
+        }
+        self.heap.clear();
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.slot_of(id).is_some()
+    }
+
+    // ids must be unique, same as for MinHeap
+    pub fn insert(&mut self, item: (usize, K)) {
+        let id = item.0;
+        assert!(id != usize::MAX, "id too large: {id}");
+        debug_assert!(!self.contains(id), "duplicate id {id} inserted");
+        if id >= self.positions.len() {
+            self.positions.resize(id + 1, usize::MAX);
+        }
+        self.heap.push(item);
+        self.positions[id] = self.heap.len() - 1;
+        self.fix(self.heap.len() - 1);
+    }
+
+    pub fn peek_min(&self) -> Option<&(usize, K)> {
+        self.heap.first()
+    }
+
+    pub fn peek_max(&self) -> Option<&(usize, K)> {
+        self.max_slot().map(|i| &self.heap[i])
+    }
+
+    pub fn delete_min(&mut self) -> Option<(usize, K)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    pub fn delete_max(&mut self) -> Option<(usize, K)> {
+        let slot = self.max_slot()?;
+        Some(self.remove_at(slot))
+    }
+
+    // remove id wherever it is, None if it isn't in the heap
+    pub fn delete(&mut self, id: usize) -> Option<(usize, K)> {
+        let slot = self.slot_of(id)?;
+        Some(self.remove_at(slot))
+    }
+
+    // set a new key in either direction, no-op for absent ids
+    pub fn change_key(&mut self, id: usize, new_key: K) {
+        if let Some(slot) = self.slot_of(id) {
+            self.heap[slot].1 = new_key;
+            self.fix(slot);
+        }
+    }
+
+    pub fn decrease_key(&mut self, id: usize, new_key: K) {
+        debug_assert!(
+            self.slot_of(id)
+                .is_none_or(|p| new_key.partial_cmp(&self.heap[p].1) != Some(Ordering::Greater)),
+            "decrease_key: new key is larger than the current one"
+        );
+        self.change_key(id, new_key);
+    }
+
+    pub fn increase_key(&mut self, id: usize, new_key: K) {
+        debug_assert!(
+            self.slot_of(id)
+                .is_none_or(|p| new_key.partial_cmp(&self.heap[p].1) != Some(Ordering::Less)),
+            "increase_key: new key is smaller than the current one"
+        );
+        self.change_key(id, new_key);
+    }
+
+    // panics unless both level invariants hold and positions is an exact
+    // inverse of the backing array, O(n log n)
+    pub fn assert_valid(&self) {
+        for (idx, (id, key)) in self.heap.iter().enumerate() {
+            assert_eq!(
+                self.positions.get(*id),
+                Some(&idx),
+                "positions[{id}] doesn't point at heap index {idx}"
+            );
+            // every ancestor on a min level is <= key, on a max level >= key
+            let mut a = idx;
+            while a > 0 {
+                a = (a - 1) / 2;
+                let anc = &self.heap[a].1;
+                if is_min_level(a) {
+                    assert_ne!(
+                        anc.partial_cmp(key),
+                        Some(Ordering::Greater),
+                        "min level {a} is above a smaller key at {idx}"
+                    );
+                } else {
+                    assert_ne!(
+                        anc.partial_cmp(key),
+                        Some(Ordering::Less),
+                        "max level {a} is above a larger key at {idx}"
+                    );
+                }
+            }
+        }
+        let live = self.positions.iter().filter(|&&p| p != usize::MAX).count();
+        assert_eq!(live, self.heap.len(), "positions has stale entries");
+    }
+
+    fn slot_of(&self, id: usize) -> Option<usize> {
+        self.positions.get(id).copied().filter(|&p| p != usize::MAX)
+    }
+
+    // the max is the root or the larger of its two children
+    fn max_slot(&self) -> Option<usize> {
+        match self.heap.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(if self.gt(2, 1) { 2 } else { 1 }),
+        }
+    }
+
+    fn remove_at(&mut self, slot: usize) -> (usize, K) {
+        let last = self.heap.len() - 1;
+        self.swap(slot, last);
+        let item = self.heap.pop().unwrap();
+        self.positions[item.0] = usize::MAX;
+        if slot < self.heap.len() {
+            self.fix(slot);
+        }
+        item
+    }
+
+    // restore both invariants around a slot whose key may have moved in
+    // either direction. if the key crossed its parent (which sits on the
+    // opposite kind of level) the two swap: the key goes up the parent's
+    // levels and the parent's old key, now on the wrong kind of level for
+    // the subtree below, trickles down. otherwise the key can only move up
+    // its own kind of level or down
+    fn fix(&mut self, i: usize) {
+        let parent = if i > 0 { Some((i - 1) / 2) } else { None };
+        if is_min_level(i) {
+            match parent {
+                Some(p) if self.gt(i, p) => {
+                    self.swap(i, p);
+                    self.push_up(p, false);
+                    self.push_down(i, true);
+                }
+                _ => {
+                    self.push_up(i, true);
+                    self.push_down(i, true);
+                }
+            }
+        } else {
+            match parent {
+                Some(p) if self.lt(i, p) => {
+                    self.swap(i, p);
+                    self.push_up(p, true);
+                    self.push_down(i, false);
+                }
+                _ => {
+                    self.push_up(i, false);
+                    self.push_down(i, false);
+                }
+            }
+        }
+    }
+
+    // move up through grandparents, i.e. along one kind of level
+    fn push_up(&mut self, mut i: usize, min: bool) {
+        while i > 2 {
+            let g = (i - 3) / 4;
+            if !self.before(i, g, min) {
+                break;
+            }
+            self.swap(i, g);
+            i = g;
+        }
+    }
+
+    // trickle down on a min (or max) level: swap with the most extreme of
+    // the children and grandchildren while that one should come first
+    fn push_down(&mut self, mut i: usize, min: bool) {
+        let n = self.heap.len();
+        loop {
+            let first_child = 2 * i + 1;
+            if first_child >= n {
+                break;
+            }
+            let mut m = first_child;
+            let candidates = [2 * i + 2, 4 * i + 3, 4 * i + 4, 4 * i + 5, 4 * i + 6];
+            for c in candidates.into_iter().filter(|&c| c < n) {
+                if self.before(c, m, min) {
+                    m = c;
+                }
+            }
+            if !self.before(m, i, min) {
+                break;
+            }
+            self.swap(m, i);
+            if m <= 2 * i + 2 {
+                // a child: nothing below it is out of place
+                break;
+            }
+            // a grandchild: the key that came down may now belong above its
+            // new parent, which is on the opposite kind of level
+            let p = (m - 1) / 2;
+            if self.before(p, m, min) {
+                self.swap(m, p);
+            }
+            i = m;
+        }
+    }
+
+    // whether slot a should sit above slot b on a min (or max) level
+    fn before(&self, a: usize, b: usize, min: bool) -> bool {
+        if min {
+            self.lt(a, b)
+        } else {
+            self.gt(a, b)
+        }
+    }
+
+    fn lt(&self, a: usize, b: usize) -> bool {
+        self.heap[a].1 < self.heap[b].1
+    }
+
+    fn gt(&self, a: usize, b: usize) -> bool {
+        self.heap[a].1 > self.heap[b].1
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].0] = a;
+        self.positions[self.heap[b].0] = b;
+    }
+}
+
+fn is_min_level(i: usize) -> bool {
+    (i + 1).ilog2().is_multiple_of(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinMaxHeap;
+
+    fn keys(n: usize, seed: u64) -> Vec<(usize, u64)> {
+        (0..n)
+            .map(|id| (id, (id as u64 * 2654435761 + seed) % 1009))
+            .collect()
+    }
+
+    #[test]
+    fn drains_from_both_ends() {
+        for n in [0, 1, 2, 3, 7, 100, 257] {
+            let mut h = MinMaxHeap::new();
+            let mut oracle: Vec<(u64, usize)> = Vec::new();
+            for (id, k) in keys(n, 17) {
+                h.insert((id, k));
+                oracle.push((k, id));
+            }
+            oracle.sort();
+            h.assert_valid();
+
+            let mut step = 0;
+            while !oracle.is_empty() {
+                assert_eq!(h.peek_min().map(|e| e.1), Some(oracle[0].0));
+                assert_eq!(h.peek_max().map(|e| e.1), oracle.last().map(|e| e.0));
+                // compare keys only, ties may come out in any id order
+                if step % 3 == 1 {
+                    let (k, _) = oracle.pop().unwrap();
+                    assert_eq!(h.delete_max().map(|e| e.1), Some(k));
+                } else {
+                    let (k, _) = oracle.remove(0);
+                    assert_eq!(h.delete_min().map(|e| e.1), Some(k));
+                }
+                h.assert_valid();
+                step += 1;
+            }
+            assert!(h.is_empty());
+            assert_eq!(h.delete_min(), None);
+            assert_eq!(h.delete_max(), None);
+        }
+    }
+
+    #[test]
+    fn key_changes() {
+        let mut h = MinMaxHeap::new();
+        for (id, k) in keys(200, 3) {
+            h.insert((id, k + 10));
+        }
+        let mut expected: Vec<(usize, u64)> = keys(200, 3)
+            .into_iter()
+            .map(|(id, k)| (id, k + 10))
+            .collect();
+
+        for id in (0..200).step_by(7) {
+            h.decrease_key(id, expected[id].1 - 10);
+            expected[id].1 -= 10;
+            h.assert_valid();
+        }
+        for id in (3..200).step_by(11) {
+            h.increase_key(id, expected[id].1 + 500);
+            expected[id].1 += 500;
+            h.assert_valid();
+        }
+        h.decrease_key(150, 0);
+        expected[150].1 = 0;
+        h.increase_key(42, 5000);
+        expected[42].1 = 5000;
+        assert_eq!(h.peek_min(), Some(&(150, 0)));
+        assert_eq!(h.peek_max(), Some(&(42, 5000)));
+
+        assert_eq!(h.delete(99), Some(expected[99]));
+        assert!(!h.contains(99));
+        h.assert_valid();
+
+        let mut popped: Vec<u64> = std::iter::from_fn(|| h.delete_min().map(|e| e.1)).collect();
+        let mut want: Vec<u64> = expected.iter().filter(|e| e.0 != 99).map(|e| e.1).collect();
+        want.sort();
+        assert_eq!(popped, want);
+
+        popped.clear();
+        h.insert((1, 5));
+        h.clear();
+        assert!(h.is_empty() && !h.contains(1));
+    }
+}
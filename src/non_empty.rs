@@ -0,0 +1,106 @@
+use crate::MinHeap;
+
+// a MinHeap that always holds at least one item, so the min is available
+// without an Option. there is no way to remove items in place; delete_min
+// consumes the wrapper and hands back a plain MinHeap that may be empty
+#[derive(Clone)]
+pub struct NonEmptyMinHeap<K> {
+    heap: MinHeap<K>,
+}
+
+impl<K: PartialOrd + Clone> NonEmptyMinHeap<K> {
+    // ids must be unique across first and rest, same as for MinHeap
+    pub fn new(first: (usize, K), rest: impl IntoIterator<Item = (usize, K)>) -> Self {
+        let mut items = vec![first];
+        items.extend(rest);
+        NonEmptyMinHeap {
+            heap: MinHeap::build_heap(items),
+        }
+    }
+
+    // None if heap is empty
+    pub fn from_heap(heap: MinHeap<K>) -> Option<Self> {
+        if heap.is_empty() {
+            None
+        } else {
+            Some(NonEmptyMinHeap { heap })
+        }
+    }
+
+    // always >= 1, hence no is_empty
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn insert(&mut self, item: (usize, K)) {
+        self.heap.insert(item);
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.heap.contains(id)
+    }
+
+    pub fn get_min(&self) -> &(usize, K) {
+        self.heap.get_min().expect("NonEmptyMinHeap is never empty")
+    }
+
+    pub fn peek(&self) -> (usize, K) {
+        self.get_min().clone()
+    }
+
+    pub fn decrease_key(&mut self, id: usize, new_key: K) {
+        self.heap.decrease_key(id, new_key);
+    }
+
+    // pop the min, the rest comes back as an ordinary (maybe empty) heap
+    pub fn delete_min(self) -> ((usize, K), MinHeap<K>) {
+        let mut heap = self.heap;
+        let min = heap.delete_min().expect("NonEmptyMinHeap is never empty");
+        (min, heap)
+    }
+
+    pub fn as_heap(&self) -> &MinHeap<K> {
+        &self.heap
+    }
+
+    pub fn into_heap(self) -> MinHeap<K> {
+        self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NonEmptyMinHeap;
+    use crate::MinHeap;
+
+    #[test]
+    fn accessors_and_transition() {
+        let mut h = NonEmptyMinHeap::new((0, 30), vec![(1, 10), (2, 20)]);
+        assert_eq!(h.len(), 3);
+        assert_eq!(*h.get_min(), (1, 10));
+
+        h.insert((3, 5));
+        h.decrease_key(2, 1);
+        assert_eq!(h.peek(), (2, 1));
+        assert!(h.contains(3));
+
+        let (min, rest) = h.delete_min();
+        assert_eq!(min, (2, 1));
+        assert_eq!(rest.len(), 3);
+        rest.assert_valid();
+
+        // the last pop leaves an empty regular heap
+        let single = NonEmptyMinHeap::new((7, 'x'), []);
+        assert_eq!(*single.get_min(), (7, 'x'));
+        let (min, rest) = single.delete_min();
+        assert_eq!(min, (7, 'x'));
+        assert!(rest.is_empty());
+
+        assert!(NonEmptyMinHeap::from_heap(rest).is_none());
+        let mut back = MinHeap::new();
+        back.insert((4, 'y'));
+        let h = NonEmptyMinHeap::from_heap(back).unwrap();
+        assert_eq!(h.into_heap().len(), 1);
+    }
+}
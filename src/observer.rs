@@ -0,0 +1,11 @@
+// callbacks fired by MinHeap operations, e.g. for counting or timing.
+// every method has an empty default so an observer only implements what it
+// cares about. Send + Sync so a MinHeap stays both whether or not it has
+// an observer
+pub trait HeapObserver<K>: Send + Sync {
+    fn on_insert(&mut self, _id: usize, _key: &K) {}
+    fn on_delete_min(&mut self, _id: usize, _key: &K) {}
+    // removals other than the min: delete(id) and delete_max
+    fn on_delete(&mut self, _id: usize, _key: &K) {}
+    fn on_decrease_key(&mut self, _id: usize, _key: &K) {}
+}
@@ -0,0 +1,109 @@
+use crate::HeapError;
+
+// a heap mutation as data, for logging, replaying or fuzzing. executed by
+// MinHeap::apply and FibHeap::apply
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op<K> {
+    Insert(usize, K),
+    DeleteMin,
+    DecreaseKey(usize, K),
+    Delete(usize),
+    Clear,
+}
+
+// what an applied Op produced. invalid ops come back as Failed and leave
+// the heap unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpResult<K> {
+    Done,
+    // DeleteMin, None when the heap was empty
+    Popped(Option<(usize, K)>),
+    // the entry removed by Delete
+    Deleted((usize, K)),
+    Failed(HeapError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Op, OpResult};
+    use crate::{FibHeap, HeapError, MinHeap};
+
+    fn script() -> Vec<Op<i32>> {
+        vec![
+            Op::Insert(0, 50),
+            Op::Insert(1, 20),
+            Op::Insert(2, 70),
+            Op::Insert(3, 40),
+            Op::DecreaseKey(2, 10),
+            Op::DeleteMin,
+            Op::Delete(3),
+            Op::Insert(4, 30),
+            Op::DeleteMin,
+        ]
+    }
+
+    #[test]
+    fn apply_matches_direct_calls() {
+        let mut direct = MinHeap::new();
+        for (id, k) in [(0, 50), (1, 20), (2, 70), (3, 40)] {
+            direct.insert((id, k));
+        }
+        direct.decrease_key(2, 10);
+        direct.delete_min();
+        direct.delete(3);
+        direct.insert((4, 30));
+        direct.delete_min();
+
+        let mut mh = MinHeap::new();
+        let mut fh = FibHeap::new();
+        let mut from_min = Vec::new();
+        for op in script() {
+            from_min.push(mh.apply(op.clone()));
+            assert_eq!(fh.apply(op), *from_min.last().unwrap());
+        }
+        assert_eq!(from_min[5], OpResult::Popped(Some((2, 10))));
+        assert_eq!(from_min[6], OpResult::Deleted((3, 40)));
+        assert_eq!(from_min[8], OpResult::Popped(Some((1, 20))));
+        assert!(mh == direct);
+        mh.assert_valid();
+        fh.assert_valid();
+        assert_eq!(fh.len(), mh.len());
+
+        assert_eq!(mh.apply(Op::Clear), OpResult::Done);
+        assert_eq!(fh.apply(Op::Clear), OpResult::Done);
+        assert_eq!(mh.apply(Op::DeleteMin), OpResult::Popped(None));
+        assert_eq!(fh.apply(Op::DeleteMin), OpResult::Popped(None));
+    }
+
+    #[test]
+    fn invalid_ops_fail() {
+        let mut mh: MinHeap<f64> = MinHeap::new();
+        let mut fh: FibHeap<f64> = FibHeap::new();
+        let bad = [
+            (Op::Insert(0, 1.0), OpResult::Done),
+            (
+                Op::Insert(0, 2.0),
+                OpResult::Failed(HeapError::DuplicateId(0)),
+            ),
+            (
+                Op::Insert(1, f64::NAN),
+                OpResult::Failed(HeapError::Incomparable),
+            ),
+            (
+                Op::DecreaseKey(5, 0.0),
+                OpResult::Failed(HeapError::NotPresent(5)),
+            ),
+            (
+                Op::DecreaseKey(0, 3.0),
+                OpResult::Failed(HeapError::KeyNotSmaller(0)),
+            ),
+            (Op::Delete(7), OpResult::Failed(HeapError::NotPresent(7))),
+        ];
+        for (op, expected) in bad {
+            assert_eq!(mh.apply(op.clone()), expected);
+            assert_eq!(fh.apply(op), expected);
+        }
+        assert_eq!(mh.len(), 1);
+        assert_eq!(fh.len(), 1);
+    }
+}
@@ -0,0 +1,56 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+/// A custom key comparison; `Less` means "pops first".
+pub type Comparator<K> = Arc<dyn Fn(&K, &K) -> Ordering + Send + Sync>;
+
+// how a heap orders its entries
+pub(crate) enum OrderKind<K> {
+    // K's PartialOrd, smallest first
+    Natural,
+    // K's PartialOrd, largest first
+    Reversed,
+    Custom(Comparator<K>),
+}
+
+impl<K> Clone for OrderKind<K> {
+    fn clone(&self) -> Self {
+        match self {
+            OrderKind::Natural => OrderKind::Natural,
+            OrderKind::Reversed => OrderKind::Reversed,
+            OrderKind::Custom(f) => OrderKind::Custom(Arc::clone(f)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct KeyOrder<K> {
+    pub(crate) kind: OrderKind<K>,
+    // break key ties by the smaller id
+    pub(crate) stable: bool,
+}
+
+impl<K: PartialOrd> KeyOrder<K> {
+    pub(crate) fn natural() -> Self {
+        KeyOrder {
+            kind: OrderKind::Natural,
+            stable: false,
+        }
+    }
+
+    // None if the keys are incomparable (e.g. NaN)
+    pub(crate) fn cmp_keys(&self, a: &K, b: &K) -> Option<Ordering> {
+        match &self.kind {
+            OrderKind::Natural => a.partial_cmp(b),
+            OrderKind::Reversed => b.partial_cmp(a),
+            OrderKind::Custom(f) => Some(f(a, b)),
+        }
+    }
+
+    pub(crate) fn cmp_entries(&self, a: &(usize, K), b: &(usize, K)) -> Option<Ordering> {
+        match self.cmp_keys(&a.1, &b.1)? {
+            Ordering::Equal if self.stable => Some(a.0.cmp(&b.0)),
+            ord => Some(ord),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+
+// a heap's entries frozen in heap order (ascending for the default
+// ordering), sorted once by MinHeap::freeze. read-only: min/max are the two
+// ends of the slice and lookups are binary searches, so repeated sorted
+// reads don't pay for popping
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedView<K> {
+    entries: Vec<(usize, K)>,
+}
+
+impl<K> SortedView<K> {
+    // entries must already be sorted, which freeze guarantees
+    pub(crate) fn from_sorted(entries: Vec<(usize, K)>) -> Self {
+        SortedView { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn min(&self) -> Option<&(usize, K)> {
+        self.entries.first()
+    }
+
+    pub fn max(&self) -> Option<&(usize, K)> {
+        self.entries.last()
+    }
+
+    pub fn as_slice(&self) -> &[(usize, K)] {
+        &self.entries
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (usize, K)> {
+        self.entries.iter()
+    }
+
+    // same contract as slice::binary_search_by_key: f must be monotone in
+    // the view's order. Ok(index) of a match, Err(insertion point) otherwise
+    pub fn binary_search_by_key<B: Ord, F>(&self, b: &B, f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&(usize, K)) -> B,
+    {
+        self.entries.binary_search_by_key(b, f)
+    }
+
+    pub fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&(usize, K)) -> Ordering,
+    {
+        self.entries.binary_search_by(f)
+    }
+
+    pub fn into_vec(self) -> Vec<(usize, K)> {
+        self.entries
+    }
+}
+
+impl<'a, K> IntoIterator for &'a SortedView<K> {
+    type Item = &'a (usize, K);
+    type IntoIter = std::slice::Iter<'a, (usize, K)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MinHeap;
+
+    #[test]
+    fn search_frozen_heap() {
+        let mut mh = MinHeap::new();
+        for id in 0..50 {
+            mh.insert((id, (id as u32 * 37) % 101 * 2));
+        }
+        mh.delete_min();
+        let view = mh.freeze();
+
+        assert_eq!(view.len(), 49);
+        assert!(view.as_slice().windows(2).all(|w| w[0].1 <= w[1].1));
+        assert_eq!(view.min().map(|e| e.1), view.iter().map(|e| e.1).min());
+        assert_eq!(view.max().map(|e| e.1), view.iter().map(|e| e.1).max());
+
+        // every key is even, so odd keys are absent
+        let present = view.as_slice()[10];
+        let found = view.binary_search_by_key(&present.1, |e| e.1).unwrap();
+        assert_eq!(view.as_slice()[found].1, present.1);
+        assert!(view
+            .binary_search_by_key(&(present.1 + 1), |e| e.1)
+            .is_err());
+        assert_eq!(view.binary_search_by_key(&1000, |e| e.1), Err(49));
+
+        let empty = MinHeap::<u32>::new().freeze();
+        assert!(empty.is_empty() && empty.min().is_none() && empty.max().is_none());
+    }
+}
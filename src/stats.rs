@@ -0,0 +1,119 @@
+//! Online statistics built on the addressable heaps.
+
+use std::cmp::Reverse;
+
+use crate::MinHeap;
+
+/// Running median over `(id, key)` pairs with removal by id.
+///
+/// The lower half lives in a max-heap (a `MinHeap` of `Reverse` keys), the
+/// upper half in a min-heap. The lower half holds the extra element when the
+/// count is odd, so `median` is the lower median for even counts.
+pub struct RunningMedian<K> {
+    lower: MinHeap<Reverse<K>>,
+    upper: MinHeap<K>,
+}
+
+impl<K: PartialOrd + Clone> Default for RunningMedian<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone> RunningMedian<K> {
+    pub fn new() -> Self {
+        RunningMedian {
+            lower: MinHeap::new(),
+            upper: MinHeap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+
+    pub fn push(&mut self, id: usize, key: K) {
+        match self.lower.get_min() {
+            Some((_, Reverse(max_low))) if key > *max_low => self.upper.insert((id, key)),
+            _ => self.lower.insert((id, Reverse(key))),
+        }
+        self.rebalance();
+    }
+
+    /// Remove `id` from whichever half holds it and return its key.
+    pub fn remove(&mut self, id: usize) -> Option<K> {
+        let key = match self.lower.delete(id) {
+            Some((_, Reverse(key))) => key,
+            None => self.upper.delete(id)?.1,
+        };
+        self.rebalance();
+        Some(key)
+    }
+
+    /// The median, or the lower of the two middle keys for an even count.
+    pub fn median(&self) -> Option<K> {
+        self.lower.get_min().map(|(_, Reverse(k))| k.clone())
+    }
+
+    // keep lower.len() == upper.len() or upper.len() + 1
+    fn rebalance(&mut self) {
+        if self.lower.len() > self.upper.len() + 1 {
+            let (id, Reverse(key)) = self.lower.delete_min().unwrap();
+            self.upper.insert((id, key));
+        } else if self.upper.len() > self.lower.len() {
+            let (id, key) = self.upper.delete_min().unwrap();
+            self.lower.insert((id, Reverse(key)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunningMedian;
+
+    fn oracle(keys: &[i32]) -> Option<i32> {
+        let mut sorted = keys.to_vec();
+        sorted.sort();
+        sorted.get(sorted.len().wrapping_sub(1) / 2).copied()
+    }
+
+    #[test]
+    fn median_odd_and_even_streams() {
+        let stream: Vec<i32> = (0..25).map(|i| (i * 17 + 3) % 31).collect();
+        let mut rm = RunningMedian::new();
+        assert_eq!(rm.median(), None);
+        for (id, &key) in stream.iter().enumerate() {
+            rm.push(id, key);
+            assert_eq!(
+                rm.median(),
+                oracle(&stream[..=id]),
+                "after {} pushes",
+                id + 1
+            );
+        }
+        assert_eq!(rm.len(), stream.len());
+    }
+
+    #[test]
+    fn median_after_deletions() {
+        let stream: Vec<i32> = (0..20).map(|i| (i * 13 + 5) % 23).collect();
+        let mut rm = RunningMedian::new();
+        for (id, &key) in stream.iter().enumerate() {
+            rm.push(id, key);
+        }
+
+        let mut live: Vec<(usize, i32)> = stream.iter().copied().enumerate().collect();
+        for id in [0, 7, 3, 19, 12, 8, 1] {
+            assert_eq!(rm.remove(id), Some(stream[id]));
+            live.retain(|&(i, _)| i != id);
+            let keys: Vec<i32> = live.iter().map(|&(_, k)| k).collect();
+            assert_eq!(rm.median(), oracle(&keys));
+        }
+        assert_eq!(rm.remove(0), None);
+        assert_eq!(rm.len(), 13);
+    }
+}
@@ -0,0 +1,155 @@
+//! Thread-safe priority queue built on `MinHeap`.
+//!
+//! `ConcurrentMinHeap` is coarse-grained by design: a single `Mutex` guards
+//! the whole heap. The batched operations (`insert_many`, `drain_min_batch`)
+//! exist so producers and consumers can amortise that one lock over many
+//! items instead of paying for it per element.
+
+use std::sync::{Condvar, Mutex};
+
+use crate::MinHeap;
+
+pub struct ConcurrentMinHeap<K> {
+    heap: Mutex<MinHeap<K>>,
+    // signalled whenever items are inserted
+    not_empty: Condvar,
+}
+
+impl<K: PartialOrd + Clone> Default for ConcurrentMinHeap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: PartialOrd + Clone> ConcurrentMinHeap<K> {
+    pub fn new() -> Self {
+        ConcurrentMinHeap {
+            heap: Mutex::new(MinHeap::new()),
+            not_empty: Condvar::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.lock().unwrap().is_empty()
+    }
+
+    // ids must be unique across all producers, same as for `MinHeap`
+    pub fn insert(&self, item: (usize, K)) {
+        self.heap.lock().unwrap().insert(item);
+        self.not_empty.notify_one();
+    }
+
+    // insert a whole batch while holding the lock once
+    pub fn insert_many<I: IntoIterator<Item = (usize, K)>>(&self, items: I) {
+        let mut heap = self.heap.lock().unwrap();
+        let before = heap.len();
+        for item in items {
+            heap.insert(item);
+        }
+        let added = heap.len() > before;
+        drop(heap);
+
+        if added {
+            self.not_empty.notify_all();
+        }
+    }
+
+    pub fn try_pop(&self) -> Option<(usize, K)> {
+        self.heap.lock().unwrap().delete_min()
+    }
+
+    // wait until an item is available, then pop the minimum
+    pub fn blocking_pop(&self) -> (usize, K) {
+        let mut heap = self.heap.lock().unwrap();
+        loop {
+            if let Some(item) = heap.delete_min() {
+                return item;
+            }
+            heap = self.not_empty.wait(heap).unwrap();
+        }
+    }
+
+    // pop up to n items in non-decreasing key order under a single lock
+    pub fn drain_min_batch(&self, n: usize) -> Vec<(usize, K)> {
+        let mut heap = self.heap.lock().unwrap();
+        let mut out = Vec::with_capacity(n.min(heap.len()));
+        while out.len() < n {
+            match heap.delete_min() {
+                Some(item) => out.push(item),
+                None => break,
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConcurrentMinHeap;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn insert_many_and_drain_batch() {
+        let q = ConcurrentMinHeap::new();
+        q.insert_many(vec![(0, 30), (1, 10), (2, 20)]);
+        assert_eq!(q.len(), 3);
+        assert_eq!(q.drain_min_batch(2), vec![(1, 10), (2, 20)]);
+        assert_eq!(q.drain_min_batch(5), vec![(0, 30)]);
+        assert!(q.is_empty());
+        assert!(q.try_pop().is_none());
+    }
+
+    #[test]
+    fn producers_and_single_consumer() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 500;
+        let total = PRODUCERS * PER_PRODUCER;
+
+        let q = Arc::new(ConcurrentMinHeap::new());
+
+        let consumer = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || {
+                let mut seen = Vec::with_capacity(total);
+                while seen.len() < total {
+                    seen.push(q.blocking_pop());
+                    let batch = q.drain_min_batch(16);
+                    assert!(batch.windows(2).all(|w| w[0].1 <= w[1].1));
+                    seen.extend(batch);
+                }
+                seen
+            })
+        };
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    let base = p * PER_PRODUCER;
+                    for chunk in (0..PER_PRODUCER).collect::<Vec<_>>().chunks(50) {
+                        let items: Vec<_> = chunk
+                            .iter()
+                            .map(|&i| (base + i, ((base + i) * 7919 % 1000) as u32))
+                            .collect();
+                        q.insert_many(items);
+                    }
+                })
+            })
+            .collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut seen = consumer.join().unwrap();
+
+        seen.sort();
+        let ids: Vec<usize> = seen.iter().map(|&(id, _)| id).collect();
+        assert_eq!(ids, (0..total).collect::<Vec<_>>());
+        assert!(q.is_empty());
+    }
+}